@@ -1,5 +1,6 @@
 use cancellation::CancellationToken;
 use crossbeam::channel::Sender;
+use std::io::Result;
 use std::sync::Arc;
 
 pub struct HashProgress {
@@ -7,7 +8,7 @@ pub struct HashProgress {
 }
 
 pub trait BlockHasher {
-    fn read(&mut self) -> usize;
+    fn read(&mut self) -> Result<usize>;
     fn update(&mut self, byte_count: usize);
     fn digest(&mut self) -> String;
     fn set_bytes_processed_event_sender(&mut self, sender: Sender<HashProgress>);
@@ -19,7 +20,7 @@ pub trait BlockHasher {
     fn bytes_processed_notification_block_size(&self) -> u64;
     fn is_bytes_processed_event_sender_defined(&self) -> bool;
     fn handle_bytes_processed_event(&self, args: HashProgress);
-    fn compute(&mut self, cancellation_token: Arc<CancellationToken>) {
+    fn compute(&mut self, cancellation_token: Arc<CancellationToken>) -> Result<u64> {
         let mut bytes_read;
         let mut running_notification_block_size = 0u64;
         let mut bytes_processed = 0u64;
@@ -30,13 +31,13 @@ pub trait BlockHasher {
                 break;
             }
 
-            bytes_read = self.read();
+            bytes_read = self.read()?;
             if bytes_read > 0 {
                 self.update(bytes_read);
+                bytes_processed += bytes_read as u64;
                 if self.is_bytes_processed_event_sender_defined()
                     && bytes_processed_notification_block_size > 0
                 {
-                    bytes_processed += bytes_read as u64;
                     running_notification_block_size += bytes_read as u64;
                     if running_notification_block_size >= bytes_processed_notification_block_size
                         || bytes_read == 0
@@ -53,5 +54,7 @@ pub trait BlockHasher {
                 break;
             }
         }
+
+        Ok(bytes_processed)
     }
 }
@@ -0,0 +1,299 @@
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::hash_file_process::HashFileProcessOptions;
+use crate::{HashFileCompression, HashType};
+
+pub static CONFIG_DIR_NAME: &str = "hshchk";
+pub static CONFIG_FILE_NAME: &str = "config.toml";
+pub static TREE_CONFIG_FILE_NAME: &str = ".hshchk.toml";
+pub static ENV_VAR_PREFIX: &str = "HSHCHK_";
+
+// Default values for command-line options. Keys are named after the long
+// command-line flags (e.g. `type`, `sum`, `min-size`). Sources are merged with
+// `merge`, where values from the merged config take precedence.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    #[serde(rename = "type")]
+    pub hash_type: Option<String>,
+    pub sum: Option<bool>,
+    pub size: Option<bool>,
+    pub extra: Option<bool>,
+    pub silent: Option<bool>,
+    #[serde(rename = "match")]
+    pub match_pattern: Option<String>,
+    #[serde(rename = "ignore")]
+    pub ignore_pattern: Option<String>,
+    pub match_absolute: Option<bool>,
+    pub exclude: Option<Vec<String>>,
+    pub include: Option<Vec<String>>,
+    pub gitignore: Option<bool>,
+    pub min_size: Option<String>,
+    pub max_size: Option<String>,
+    pub newer_than: Option<String>,
+    pub older_than: Option<String>,
+    pub log: Option<String>,
+    pub public_key: Option<String>,
+    pub header: Option<bool>,
+    pub compress: Option<String>,
+    pub stream: Option<bool>,
+    pub no_walk: Option<bool>,
+    pub per_directory: Option<bool>,
+}
+
+impl Config {
+    pub fn from_toml_str(toml_str: &str) -> Result<Config, String> {
+        toml::from_str(toml_str).map_err(|error| error.to_string())
+    }
+    // Returns `None` when the file doesn't exist.
+    pub fn from_file(path: &Path) -> Result<Option<Config>, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => Config::from_toml_str(&content).map(Some).map_err(|error| {
+                format!("Invalid configuration file {}: {}", path.display(), error)
+            }),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(format!("Couldn't read {}: {}", path.display(), error)),
+        }
+    }
+    // Reads `HSHCHK_*` variables, e.g. `HSHCHK_TYPE=blake3` or `HSHCHK_MIN_SIZE=1G`.
+    // Boolean variables accept 1/0, true/false and yes/no. List variables
+    // (`HSHCHK_EXCLUDE`, `HSHCHK_INCLUDE`) are comma separated.
+    pub fn from_env_vars<I: IntoIterator<Item = (String, String)>>(
+        vars: I,
+    ) -> Result<Config, String> {
+        let mut config = Config::default();
+        for (name, value) in vars {
+            let key = match name.strip_prefix(ENV_VAR_PREFIX) {
+                Some(key) => key,
+                None => continue,
+            };
+            match key {
+                "TYPE" => config.hash_type = Some(value),
+                "SUM" => config.sum = Some(parse_env_bool(&name, &value)?),
+                "SIZE" => config.size = Some(parse_env_bool(&name, &value)?),
+                "EXTRA" => config.extra = Some(parse_env_bool(&name, &value)?),
+                "SILENT" => config.silent = Some(parse_env_bool(&name, &value)?),
+                "MATCH" => config.match_pattern = Some(value),
+                "IGNORE" => config.ignore_pattern = Some(value),
+                "MATCH_ABSOLUTE" => config.match_absolute = Some(parse_env_bool(&name, &value)?),
+                "EXCLUDE" => config.exclude = Some(parse_env_list(&value)),
+                "INCLUDE" => config.include = Some(parse_env_list(&value)),
+                "GITIGNORE" => config.gitignore = Some(parse_env_bool(&name, &value)?),
+                "MIN_SIZE" => config.min_size = Some(value),
+                "MAX_SIZE" => config.max_size = Some(value),
+                "NEWER_THAN" => config.newer_than = Some(value),
+                "OLDER_THAN" => config.older_than = Some(value),
+                "LOG" => config.log = Some(value),
+                "PUBLIC_KEY" => config.public_key = Some(value),
+                "HEADER" => config.header = Some(parse_env_bool(&name, &value)?),
+                "COMPRESS" => config.compress = Some(value),
+                "STREAM" => config.stream = Some(parse_env_bool(&name, &value)?),
+                "NO_WALK" => config.no_walk = Some(parse_env_bool(&name, &value)?),
+                "PER_DIRECTORY" => config.per_directory = Some(parse_env_bool(&name, &value)?),
+                _ => (), // Unrelated variables (e.g. a future setting) are ignored.
+            }
+        }
+
+        Ok(config)
+    }
+    pub fn from_env() -> Result<Config, String> {
+        Config::from_env_vars(env::vars())
+    }
+    // Values set in `other` take precedence over values set in `self`.
+    pub fn merge(self, other: Config) -> Config {
+        Config {
+            hash_type: other.hash_type.or(self.hash_type),
+            sum: other.sum.or(self.sum),
+            size: other.size.or(self.size),
+            extra: other.extra.or(self.extra),
+            silent: other.silent.or(self.silent),
+            match_pattern: other.match_pattern.or(self.match_pattern),
+            ignore_pattern: other.ignore_pattern.or(self.ignore_pattern),
+            match_absolute: other.match_absolute.or(self.match_absolute),
+            exclude: other.exclude.or(self.exclude),
+            include: other.include.or(self.include),
+            gitignore: other.gitignore.or(self.gitignore),
+            min_size: other.min_size.or(self.min_size),
+            max_size: other.max_size.or(self.max_size),
+            newer_than: other.newer_than.or(self.newer_than),
+            older_than: other.older_than.or(self.older_than),
+            log: other.log.or(self.log),
+            public_key: other.public_key.or(self.public_key),
+            header: other.header.or(self.header),
+            compress: other.compress.or(self.compress),
+            stream: other.stream.or(self.stream),
+            no_walk: other.no_walk.or(self.no_walk),
+            per_directory: other.per_directory.or(self.per_directory),
+        }
+    }
+    pub fn get_hash_type(&self) -> Result<Option<HashType>, String> {
+        self.hash_type
+            .as_ref()
+            .map(|hash_type| {
+                hash_type
+                    .to_uppercase()
+                    .parse()
+                    .ok()
+                    .filter(|hash_type| *hash_type != HashType::CRC32)
+                    .ok_or_else(|| format!("Invalid hash function type: {}", hash_type))
+            })
+            .transpose()
+    }
+    pub fn get_hash_file_compression(&self) -> Result<Option<HashFileCompression>, String> {
+        self.compress
+            .as_ref()
+            .map(|compress| {
+                compress
+                    .to_lowercase()
+                    .parse()
+                    .map_err(|_| format!("Invalid compression: {}", compress))
+            })
+            .transpose()
+    }
+    pub fn to_options(&self, base_path: &Path) -> Result<HashFileProcessOptions<'_>, String> {
+        Ok(HashFileProcessOptions {
+            base_path: base_path.to_path_buf(),
+            hash_file_format: self.sum.map(crate::get_hash_file_format_from_arg),
+            hash_file_compression: self.get_hash_file_compression()?,
+            hash_type: self.get_hash_type()?,
+            report_extra: self.extra,
+            size_only: self.size,
+            match_pattern: self.match_pattern.as_deref(),
+            ignore_pattern: self.ignore_pattern.as_deref(),
+            match_absolute_path: self.match_absolute,
+            exclude_patterns: get_glob_patterns(&self.exclude)?,
+            include_patterns: get_glob_patterns(&self.include)?,
+            use_gitignore: self.gitignore,
+            min_size: self
+                .min_size
+                .as_deref()
+                .map(crate::get_size_from_str)
+                .transpose()?,
+            max_size: self
+                .max_size
+                .as_deref()
+                .map(crate::get_size_from_str)
+                .transpose()?,
+            newer_than: self
+                .newer_than
+                .as_deref()
+                .map(crate::get_time_from_age_str)
+                .transpose()?,
+            older_than: self
+                .older_than
+                .as_deref()
+                .map(crate::get_time_from_age_str)
+                .transpose()?,
+            header: self.header,
+            stream: self.stream,
+            no_walk: self.no_walk,
+            per_directory: self.per_directory,
+            ..Default::default()
+        })
+    }
+}
+
+fn get_glob_patterns(patterns: &Option<Vec<String>>) -> Result<Option<Vec<&str>>, String> {
+    patterns
+        .as_ref()
+        .map(|patterns| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    crate::ignore_filter::check_glob_pattern(pattern).map(|_| pattern.as_str())
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+// `$XDG_CONFIG_HOME/hshchk/config.toml`, falling back to `~/.config` as per the
+// XDG base directory specification.
+pub fn get_user_config_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|config_path| config_path.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+pub fn get_tree_config_path(base_path: &Path) -> PathBuf {
+    base_path.join(TREE_CONFIG_FILE_NAME)
+}
+
+// Merges defaults in increasing order of precedence: the user config file, the
+// per-tree config file (when a base path is given) and the environment.
+// Command-line flags are expected to be merged last.
+pub fn load_defaults(base_path: Option<&Path>) -> Result<Config, String> {
+    let mut config = Config::default();
+    if let Some(user_config_path) = get_user_config_path() {
+        if let Some(user_config) = Config::from_file(&user_config_path)? {
+            config = config.merge(user_config);
+        }
+    }
+
+    if let Some(base_path) = base_path {
+        let tree_config_path = get_tree_config_path(base_path);
+        if let Some(tree_config) = Config::from_file(&tree_config_path)? {
+            check_tree_config(&tree_config, &tree_config_path)?;
+            config = config.merge(tree_config);
+        }
+    }
+
+    Ok(config.merge(Config::from_env()?))
+}
+
+// Per-tree config files are stored alongside the checksum files, so whoever
+// provides a tree can't be trusted with the public key used to authenticate its
+// checksum files, nor with the path of the log file, nor with options that skip
+// files or checks when verifying it.
+fn check_tree_config(config: &Config, path: &Path) -> Result<(), String> {
+    for (name, is_set) in &[
+        ("public-key", config.public_key.is_some()),
+        ("log", config.log.is_some()),
+        ("size", config.size == Some(true)),
+        ("match", config.match_pattern.is_some()),
+        ("ignore", config.ignore_pattern.is_some()),
+        ("match-absolute", config.match_absolute == Some(true)),
+        ("exclude", config.exclude.is_some()),
+        ("include", config.include.is_some()),
+        ("gitignore", config.gitignore == Some(true)),
+        ("min-size", config.min_size.is_some()),
+        ("max-size", config.max_size.is_some()),
+        ("newer-than", config.newer_than.is_some()),
+        ("older-than", config.older_than.is_some()),
+        ("no-walk", config.no_walk == Some(true)),
+    ] {
+        if *is_set {
+            return Err(format!(
+                "Invalid configuration file {}: {} can't be set in a per-tree configuration \
+                 file.",
+                path.display(),
+                name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_env_bool(name: &str, value: &str) -> Result<bool, String> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" => Ok(true),
+        "0" | "false" | "no" | "" => Ok(false),
+        _ => Err(format!("Invalid boolean value for {}: {}", name, value)),
+    }
+}
+
+fn parse_env_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
@@ -0,0 +1,29 @@
+use crc32fast::Hasher;
+use digest::generic_array::typenum::U4;
+use digest::{FixedOutputDirty, Output, Reset, Update};
+
+// `digest` adapter for CRC-32 (IEEE), as used by SFV files. Checksums are
+// encoded in big-endian order, like `cksfv` does.
+
+#[derive(Clone, Default)]
+pub struct Crc32(Hasher);
+
+impl Update for Crc32 {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.0.update(data.as_ref());
+    }
+}
+
+impl FixedOutputDirty for Crc32 {
+    type OutputSize = U4;
+
+    fn finalize_into_dirty(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.0.clone().finalize().to_be_bytes());
+    }
+}
+
+impl Reset for Crc32 {
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
@@ -1,74 +1,38 @@
 use crate::block_hasher::{BlockHasher, HashProgress};
-use crate::open_file;
+use crate::{Digest, HashType};
+use cancellation::CancellationToken;
 use crossbeam::channel::Sender;
-use digest::Digest;
-use std::io::{BufReader, Read};
+use std::fs::File;
+use std::io::Result;
 use std::path::Path;
+use std::sync::Arc;
 
-pub struct FileHash<T: Digest> {
-    reader: BufReader<std::fs::File>,
-    hasher: T,
-    buffer: Vec<u8>,
-    buffer_size: usize,
-    bytes_processed_event: Option<Sender<HashProgress>>,
-    bytes_processed_notification_block_size: u64,
+// Hashes the content of a file, read with the same hasher as `hash_reader_keyed`.
+pub struct FileHash {
+    hasher: Box<dyn BlockHasher>,
 }
 
-const DEFAULT_BUFFER_SIZE: usize = 1_048_576;
-const DEFAULT_BYTES_PROCESSED_NOTIFICATION_BLOCK_SIZE: u64 = 2_097_152;
-
-impl<T: Digest> FileHash<T> {
-    pub fn new_with_buffer_size(file_path: &Path, buffer_size: usize) -> Self {
-        FileHash {
-            reader: BufReader::new(open_file(&file_path)),
-            hasher: T::new(),
-            buffer: Vec::with_capacity(buffer_size),
-            buffer_size,
-            bytes_processed_event: None,
-            bytes_processed_notification_block_size: 0,
-        }
-    }
-    pub fn new(file_path: &Path) -> Self {
-        FileHash::new_with_buffer_size(file_path, DEFAULT_BUFFER_SIZE)
-    }
-}
-
-impl<T: Digest> BlockHasher for FileHash<T> {
-    fn read(&mut self) -> usize {
-        self.buffer.clear();
-        let mut adaptor = (&mut self.reader).take(self.buffer_size as u64);
-        adaptor.read_to_end(&mut self.buffer).unwrap()
-    }
-    fn update(&mut self, byte_count: usize) {
-        self.hasher.update(&self.buffer[..byte_count]);
-    }
-    fn digest(&mut self) -> String {
-        hex::encode(self.hasher.finalize_reset())
-    }
-    fn set_bytes_processed_event_sender(&mut self, sender: Sender<HashProgress>) {
-        self.set_bytes_processed_event_sender_with_bytes_processed_notification_block_size(
-            sender,
-            DEFAULT_BYTES_PROCESSED_NOTIFICATION_BLOCK_SIZE,
-        )
-    }
-    fn set_bytes_processed_event_sender_with_bytes_processed_notification_block_size(
-        &mut self,
-        sender: Sender<HashProgress>,
-        bytes_processed_notification_block_size: u64,
-    ) {
-        self.bytes_processed_event = Some(sender);
-        self.bytes_processed_notification_block_size = bytes_processed_notification_block_size;
-    }
-    fn is_bytes_processed_event_sender_defined(&self) -> bool {
-        self.bytes_processed_event.is_some()
-    }
-    fn bytes_processed_notification_block_size(&self) -> u64 {
-        self.bytes_processed_notification_block_size
-    }
-    fn handle_bytes_processed_event(&self, args: HashProgress) {
-        match &self.bytes_processed_event {
-            Some(sender) => sender.send(args).unwrap(),
-            None => (),
-        }
+impl FileHash {
+    pub fn open(
+        hash_type: HashType,
+        digest_length: Option<usize>,
+        key: Option<&[u8]>,
+        file_path: &Path,
+    ) -> Result<Self> {
+        Ok(FileHash {
+            hasher: crate::get_keyed_reader_hasher(
+                hash_type,
+                digest_length,
+                key,
+                File::open(file_path)?,
+            )?,
+        })
+    }
+    pub fn compute(
+        self,
+        cancellation_token: Arc<CancellationToken>,
+        progress_sender: Option<Sender<HashProgress>>,
+    ) -> Result<Digest> {
+        crate::compute_digest(self.hasher, cancellation_token, progress_sender)
     }
 }
@@ -1,11 +1,13 @@
 use cancellation::CancellationToken;
 use std::fs;
-use std::io::Result;
+use std::io::Error;
 use std::path::Path;
 use std::sync::Arc;
 
 pub trait FileTreeProcessor {
     fn process_file(&mut self, file_path: &Path);
+    fn process_error(&mut self, path: &Path, error: Error);
+    fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool;
 }
 
 pub struct FileTree<'a, T: FileTreeProcessor> {
@@ -16,26 +18,40 @@ impl<'a, T: FileTreeProcessor> FileTree<'a, T> {
     pub fn new(processor: &'a mut T) -> Self {
         FileTree { processor }
     }
-    pub fn traverse(
-        &mut self,
-        path: &Path,
-        cancellation_token: &Arc<CancellationToken>,
-    ) -> Result<()> {
+    pub fn traverse(&mut self, path: &Path, cancellation_token: &Arc<CancellationToken>) {
         if path.is_dir() {
-            for entry in fs::read_dir(path)? {
+            let entries = match fs::read_dir(path) {
+                Ok(entries) => entries,
+                Err(error) => {
+                    self.processor.process_error(path, error);
+                    return;
+                }
+            };
+
+            for entry in entries {
                 if cancellation_token.is_canceled() {
                     break;
                 }
 
-                let path = entry?.path();
-                if path.is_dir() {
-                    self.traverse(&path, cancellation_token)?;
+                let path = match entry {
+                    Ok(entry) => entry.path(),
+                    Err(error) => {
+                        self.processor.process_error(path, error);
+                        continue;
+                    }
+                };
+
+                let is_dir = path.is_dir();
+                if self.processor.is_ignored(&path, is_dir) {
+                    continue;
+                }
+
+                if is_dir {
+                    self.traverse(&path, cancellation_token);
                 } else {
                     self.processor.process_file(&path);
                 }
             }
         }
-
-        Ok(())
     }
 }
@@ -1,13 +1,42 @@
-use crate::{create_file, open_file, replaceable_separator, HashFileFormat};
+use crate::{
+    create_file, get_hash_file_compression, replaceable_separator, HashFileCompression,
+    HashFileFormat, HashType,
+};
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::fs::File;
 use std::io::{
-    prelude::{BufRead, Write},
-    BufReader, BufWriter,
+    prelude::{BufRead, Read, Write},
+    BufReader, BufWriter, Error, ErrorKind, Result,
 };
-use std::path::{Path, MAIN_SEPARATOR};
+use std::path::{Component, Path, MAIN_SEPARATOR, MAIN_SEPARATOR_STR};
+use std::time::SystemTime;
 
 const MAX_PATH_SIZE: usize = 4_096 - 1;
-const MAX_HASH_SIZE: usize = 1024;
+const XXH3_DIGEST_PREFIX: &str = "XXH3_";
+const MAX_REPORTED_LINE_ERRORS: usize = 10;
+const HEADER_LINE_PREFIX: &str = "# ";
+const COMMENT_PREFIX: char = '#';
+const SFV_COMMENT_PREFIX: char = ';';
+
+pub const SFV_EXTENSION: &str = "sfv";
+
+const HASH_FILE_VERSION: u32 = 1;
+const VERSION_HEADER_NAME: &str = "version";
+const FORMAT_HEADER_NAME: &str = "format";
+const CREATED_HEADER_NAME: &str = "created";
+const HOST_HEADER_NAME: &str = "host";
+const TOOL_HEADER_NAME: &str = "tool";
+const BASE_PATH_HEADER_NAME: &str = "base-path";
+const ENTRIES_HEADER_NAME: &str = "entries";
+const BYTES_HEADER_NAME: &str = "bytes";
+const BODY_CHECKSUM_HEADER_NAME: &str = "body-sha256";
+const HASH_CHECK_FORMAT_NAME: &str = "hshchk";
+const HASH_SUM_FORMAT_NAME: &str = "sum";
+
+pub const ALGORITHM_HEADER_NAME: &str = "algorithm";
+pub const KEYED_HEADER_NAME: &str = "keyed";
 
 // `hshchk-lib` supports well-formed Unicode file names only.
 // This is why paths are stored using `String` instead of `Path`.
@@ -15,61 +44,151 @@ const MAX_HASH_SIZE: usize = 1024;
 pub struct HashFileEntry {
     pub file_path: String,
     pub size: Option<u64>,
-    pub binary: bool,
     pub digest: String,
 }
 
+// Malformed lines are described by the error.
+type EntryParseResult = std::result::Result<HashFileEntry, String>;
+
+// Header lines are comment lines (`# name: value`) at the top of checksum files.
+// Checksum files with a metadata header (see `set_metadata`) also record their
+// entry count, total size and a checksum of the lines following the header, so
+// that truncated or edited checksum files are rejected by `load`.
 pub struct HashFile {
+    header: Vec<(String, String)>,
     files: HashMap<String, HashFileEntry>,
+    digest_length: Option<usize>,
 }
 
 impl HashFile {
     pub fn new() -> Self {
         HashFile {
+            header: Vec::new(),
             files: HashMap::new(),
+            digest_length: None,
         }
     }
 
-    pub fn load(&mut self, file_path: &Path) {
-        let hash_file_format = get_hash_file_format(file_path);
-        let file = open_file(file_path);
-        let reader = BufReader::new(&file);
-        let file_separator = replaceable_separator();
-        let os_separator = &MAIN_SEPARATOR.to_string();
-        let entry_parse = match hash_file_format {
-            HashFileFormat::HashCheck => parse_hash_check_entry,
-            _ => parse_hash_sum_entry,
-        };
+    // Entries are validated against `hash_type`. Malformed lines and unsafe paths
+    // are reported with their line numbers. Compressed checksum files are
+    // decompressed according to their extension.
+    pub fn load(&mut self, file_path: &Path, hash_type: HashType) -> Result<()> {
+        let mut hash_file_reader = HashFileReader::open(file_path, hash_type)?;
+        for (name, value) in hash_file_reader.get_header() {
+            self.set_header_value(name, value);
+        }
 
-        for (_, line) in reader.lines().enumerate() {
-            let content = line.unwrap().replace(file_separator, os_separator);
-            if let Some(file_entry) = entry_parse(&content) {
-                self.add_entry(file_entry);
-            }
+        while let Some(file_entry) = hash_file_reader.next_entry()? {
+            self.add_entry(file_entry);
         }
+
+        self.digest_length = hash_file_reader.get_digest_length();
+        hash_file_reader.finish()
     }
 
-    pub fn save(&self, file_path: &Path, hash_file_format: HashFileFormat) {
-        let file = create_file(&file_path);
-        let mut writer = BufWriter::new(&file);
-        let entry_format = match hash_file_format {
-            HashFileFormat::HashCheck => format_hash_check_entry,
-            HashFileFormat::HashSum => format_hash_sum_entry,
+    pub fn save(&self, file_path: &Path, hash_type: HashType, hash_file_format: HashFileFormat) {
+        let mut header = self.header.clone();
+        if self.has_metadata() {
+            // Entries are formatted once to compute the body checksum, then again when
+            // writing, so that the body of large checksum files isn't held in memory.
+            let mut body_hasher = Sha256::new();
+            for file_entry in self.files.values() {
+                body_hasher
+                    .update(format_entry(file_entry, hash_type, hash_file_format).as_bytes());
+            }
+
+            let total_size: u64 = self.files.values().filter_map(|entry| entry.size).sum();
+            set_header_value(
+                &mut header,
+                FORMAT_HEADER_NAME,
+                get_hash_file_format_name(hash_file_format),
+            );
+            set_header_value(
+                &mut header,
+                ENTRIES_HEADER_NAME,
+                &self.files.len().to_string(),
+            );
+            set_header_value(&mut header, BYTES_HEADER_NAME, &total_size.to_string());
+            set_header_value(
+                &mut header,
+                BODY_CHECKSUM_HEADER_NAME,
+                &hex::encode(body_hasher.finalize()),
+            );
+        }
+
+        let file = create_file(file_path);
+        let write_result = write_content(file_path, file, |writer| {
+            for (name, value) in &header {
+                writeln!(writer, "{}{}: {}", HEADER_LINE_PREFIX, name, value)?;
+            }
+
+            for file_entry in self.files.values() {
+                writer
+                    .write_all(format_entry(file_entry, hash_type, hash_file_format).as_bytes())?;
+            }
+
+            Ok(())
+        });
+        if let Err(why) = write_result {
+            panic!("Couldn't write to {}: {}.", file_path.display(), why)
         };
-        for file_entry in self.files.values() {
-            let line = &entry_format(file_entry);
-            if let Err(why) = writer.write(line.as_bytes()) {
-                panic!("Couldn't write to {}: {}.", file_path.display(), why)
-            };
+    }
+
+    // Records the metadata header. The entry count, total size and body checksum
+    // are added when saving.
+    pub fn set_metadata(
+        &mut self,
+        hash_type: HashType,
+        hash_file_format: HashFileFormat,
+        base_path: &Path,
+    ) {
+        self.set_header_value(VERSION_HEADER_NAME, &HASH_FILE_VERSION.to_string());
+        self.set_header_value(
+            FORMAT_HEADER_NAME,
+            get_hash_file_format_name(hash_file_format),
+        );
+        self.set_header_value(ALGORITHM_HEADER_NAME, hash_type.into());
+        self.set_header_value(
+            CREATED_HEADER_NAME,
+            &humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        );
+        if let Some(host_name) = get_host_name() {
+            self.set_header_value(HOST_HEADER_NAME, &host_name);
         }
+
+        self.set_header_value(
+            TOOL_HEADER_NAME,
+            &format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        );
+        self.set_header_value(BASE_PATH_HEADER_NAME, &base_path.to_string_lossy());
+    }
+
+    pub fn has_metadata(&self) -> bool {
+        self.get_header_value(VERSION_HEADER_NAME).is_some()
+    }
+
+    pub fn get_header(&self) -> &[(String, String)] {
+        &self.header
+    }
+
+    pub fn get_header_value(&self, name: &str) -> Option<&str> {
+        get_header_value(&self.header, name)
+    }
+
+    pub fn set_header_value(&mut self, name: &str, value: &str) {
+        set_header_value(&mut self.header, name, value);
     }
 
     pub fn add_entry(&mut self, file_entry: HashFileEntry) {
         self.files.insert(file_entry.file_path.clone(), file_entry);
     }
 
-    pub fn remove_entry(&mut self, file_path: &str) {
-        self.files.remove(file_path);
+    pub fn remove_entry(&mut self, file_path: &str) -> Option<HashFileEntry> {
+        self.files.remove(file_path)
+    }
+
+    pub fn retain<F: FnMut(&str) -> bool>(&mut self, mut keep: F) {
+        self.files.retain(|file_path, _| keep(file_path));
     }
 
     pub fn get_entry(&self, file_path: &str) -> Option<&HashFileEntry> {
@@ -77,7 +196,17 @@ impl HashFile {
     }
 
     pub fn get_file_paths(&self) -> Vec<String> {
-        self.files.iter().map(|(key, _)| key.clone()).collect()
+        self.files.keys().cloned().collect()
+    }
+
+    pub fn into_entries(self) -> impl Iterator<Item = HashFileEntry> {
+        self.files.into_values()
+    }
+
+    // The digest length shared by all entries of the loaded checksum file, for hash
+    // functions with a variable digest length.
+    pub fn get_digest_length(&self) -> Option<usize> {
+        self.digest_length
     }
 
     pub fn is_empty(&self) -> bool {
@@ -85,79 +214,589 @@ impl HashFile {
     }
 }
 
-fn get_hash_file_format(file_path: &Path) -> HashFileFormat {
-    let file = open_file(file_path);
-    let mut reader = BufReader::new(&file);
-    let mut first_line = String::new();
-    reader.read_line(&mut first_line).unwrap();
+// Reads the entries of a checksum file one at a time, so that large checksum files
+// can be verified without holding every entry in memory. The header is read when
+// opening. Malformed lines and unsafe paths are skipped while reading and reported,
+// along with metadata mismatches, by `finish`.
+pub struct HashFileReader {
+    reader: BufReader<Box<dyn Read>>,
+    hash_type: HashType,
+    header: Vec<(String, String)>,
+    entry_parse: fn(&str) -> EntryParseResult,
+    // Lines of the hash check format start with the file path, which may start
+    // with `#`, so comments are only recognized in the header.
+    comment_prefix: Option<char>,
+    body_hasher: Sha256,
+    entry_count: usize,
+    total_size: Option<u64>,
+    digest_length: Option<usize>,
+    malformed_lines: Vec<String>,
+    unsafe_paths: Vec<String>,
+    line: String,
+    line_number: usize,
+    pending_line: bool,
+}
+
+impl HashFileReader {
+    pub fn open(file_path: &Path, hash_type: HashType) -> Result<Self> {
+        let file = File::open(file_path)?;
+        let mut reader = BufReader::new(get_decoder(file_path, file)?);
+        let mut header = Vec::new();
+        let mut line = String::new();
+        let mut line_number = 0usize;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+
+            line_number += 1;
+            let content = line.trim_end_matches(['\n', '\r']);
+            // Header-less checksum files of the hash check format may start with a
+            // path starting with `#`.
+            if !content.starts_with(COMMENT_PREFIX) || parse_hash_check_entry(content).is_ok() {
+                break;
+            }
+
+            if let Some((name, value)) = parse_header_line(content) {
+                set_header_value(&mut header, name, value);
+            }
+        }
+
+        // SFV files are recognized by their extension. Otherwise, without a header,
+        // the format is guessed from the first line.
+        let is_sfv = get_uncompressed_extension(file_path)
+            .is_some_and(|extension| extension.eq_ignore_ascii_case(SFV_EXTENSION));
+        let (entry_parse, comment_prefix): (fn(&str) -> EntryParseResult, _) = if is_sfv {
+            (parse_sfv_entry, Some(SFV_COMMENT_PREFIX))
+        } else {
+            let hash_file_format = match get_header_value(&header, FORMAT_HEADER_NAME) {
+                Some(format_name) => get_hash_file_format_from_name(format_name)?,
+                None => get_hash_file_format(line.trim_end_matches(['\n', '\r'])),
+            };
+            match hash_file_format {
+                HashFileFormat::HashCheck => (parse_hash_check_entry, None),
+                _ => (parse_hash_sum_entry, Some(COMMENT_PREFIX)),
+            }
+        };
+        let pending_line = !line.is_empty();
+        Ok(HashFileReader {
+            reader,
+            hash_type,
+            header,
+            entry_parse,
+            comment_prefix,
+            body_hasher: Sha256::new(),
+            entry_count: 0,
+            total_size: Some(0),
+            digest_length: None,
+            malformed_lines: Vec::new(),
+            unsafe_paths: Vec::new(),
+            line,
+            line_number,
+            pending_line,
+        })
+    }
+
+    pub fn get_header(&self) -> &[(String, String)] {
+        &self.header
+    }
+
+    pub fn get_header_value(&self, name: &str) -> Option<&str> {
+        get_header_value(&self.header, name)
+    }
+
+    // Known once an entry is read, for hash functions with a variable digest length.
+    pub fn get_digest_length(&self) -> Option<usize> {
+        self.digest_length
+    }
+
+    // Returns `None` once all lines are read.
+    pub fn next_entry(&mut self) -> Result<Option<HashFileEntry>> {
+        let file_separator = replaceable_separator();
+        let os_separator = &MAIN_SEPARATOR.to_string();
+        loop {
+            if self.pending_line {
+                self.pending_line = false;
+            } else {
+                self.line.clear();
+                if self.reader.read_line(&mut self.line)? == 0 {
+                    return Ok(None);
+                }
+
+                self.line_number += 1;
+            }
+
+            self.body_hasher.update(self.line.as_bytes());
+            let content = self.line.trim_end_matches(['\n', '\r']);
+            if content.is_empty()
+                || self
+                    .comment_prefix
+                    .is_some_and(|comment_prefix| content.starts_with(comment_prefix))
+            {
+                continue;
+            }
+
+            let content = content.replace(file_separator, os_separator);
+            let mut file_entry = match (self.entry_parse)(&content).and_then(|file_entry| {
+                check_digest(&file_entry.digest, self.hash_type, &mut self.digest_length)?;
+                Ok(file_entry)
+            }) {
+                Ok(file_entry) => file_entry,
+                Err(reason) => {
+                    self.malformed_lines
+                        .push(format!("line {}: {}", self.line_number, reason));
+                    continue;
+                }
+            };
+
+            self.entry_count += 1;
+            if let Some(reason) = get_unsafe_path_reason(&file_entry.file_path) {
+                self.unsafe_paths.push(format!(
+                    "line {}: {:?}, {}",
+                    self.line_number, file_entry.file_path, reason
+                ));
+                continue;
+            }
+
+            file_entry.file_path = get_normalized_path(&file_entry.file_path);
+            self.total_size = self
+                .total_size
+                .and_then(|total_size| file_entry.size.map(|size| total_size.saturating_add(size)));
+            return Ok(Some(file_entry));
+        }
+    }
+
+    pub fn finish(self) -> Result<()> {
+        if !self.malformed_lines.is_empty() {
+            return Err(get_line_errors(
+                "Checksum file contains malformed lines",
+                self.malformed_lines,
+            ));
+        }
+
+        if !self.unsafe_paths.is_empty() {
+            return Err(get_line_errors(
+                "Checksum file contains unsafe paths",
+                self.unsafe_paths,
+            ));
+        }
+
+        if self.get_header_value(VERSION_HEADER_NAME).is_some() {
+            check_metadata(
+                &self.header,
+                self.entry_count,
+                self.total_size,
+                &hex::encode(self.body_hasher.finalize()),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn check_metadata(
+    header: &[(String, String)],
+    entry_count: usize,
+    total_size: Option<u64>,
+    body_checksum: &str,
+) -> Result<()> {
+    match get_header_value(header, VERSION_HEADER_NAME)
+        .unwrap()
+        .parse::<u32>()
+    {
+        Ok(version) if version <= HASH_FILE_VERSION => {}
+        _ => {
+            return Err(invalid_data(
+                "Checksum file was created by a newer version of hshchk.".into(),
+            ))
+        }
+    }
+
+    if let Some(expected_entry_count) = get_header_value(header, ENTRIES_HEADER_NAME) {
+        if expected_entry_count != entry_count.to_string() {
+            return Err(invalid_data(format!(
+                "Checksum file is truncated or was modified: {} entries expected, {} found.",
+                expected_entry_count, entry_count
+            )));
+        }
+    }
+
+    // Checksum files in hash sum format don't record file sizes.
+    if let (Some(expected_total_size), Some(total_size)) =
+        (get_header_value(header, BYTES_HEADER_NAME), total_size)
+    {
+        if expected_total_size != total_size.to_string() {
+            return Err(invalid_data(format!(
+                "Checksum file was modified: {} bytes expected, {} found.",
+                expected_total_size, total_size
+            )));
+        }
+    }
+
+    if let Some(expected_body_checksum) = get_header_value(header, BODY_CHECKSUM_HEADER_NAME) {
+        if !expected_body_checksum.eq_ignore_ascii_case(body_checksum) {
+            return Err(invalid_data(
+                "Checksum file is truncated or was modified: body checksum mismatch.".into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Checksum files may come from untrusted sources, so entries must refer to files
+// under the base directory.
+fn get_unsafe_path_reason(file_path: &str) -> Option<&'static str> {
+    if file_path.contains('\0') {
+        return Some("contains a NUL character");
+    }
+
+    // Windows absolute paths are rejected on all platforms.
+    let bytes = file_path.as_bytes();
+    if file_path.starts_with(['/', '\\'])
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+    {
+        return Some("absolute path");
+    }
+
+    let mut depth = 0usize;
+    for component in Path::new(file_path).components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => return Some("absolute path"),
+            Component::CurDir => {}
+            Component::ParentDir if depth == 0 => return Some("escapes the base directory"),
+            Component::ParentDir => depth -= 1,
+            Component::Normal(_) => depth += 1,
+        }
+    }
+
+    if depth == 0 {
+        return Some("refers to the base directory");
+    }
+
+    None
+}
+
+// Resolves `.` and `..` components of safe paths (e.g. `dir/../file` to `file`),
+// so that entries match the paths of the files they refer to.
+fn get_normalized_path(file_path: &str) -> String {
+    let mut components = Vec::new();
+    for component in Path::new(file_path).components() {
+        match component {
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::Normal(name) => components.push(name.to_str().unwrap()),
+            _ => {}
+        }
+    }
+
+    components.join(MAIN_SEPARATOR_STR)
+}
+
+fn get_header_value<'a>(header: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    header
+        .iter()
+        .find(|(header_name, _)| header_name == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn set_header_value(header: &mut Vec<(String, String)>, name: &str, value: &str) {
+    match header
+        .iter_mut()
+        .find(|(header_name, _)| header_name == name)
+    {
+        Some((_, header_value)) => *header_value = value.to_string(),
+        None => header.push((name.to_string(), value.to_string())),
+    }
+}
+
+fn get_hash_file_format(first_line: &str) -> HashFileFormat {
     match first_line.find('|') {
         Some(_) => HashFileFormat::HashCheck,
         _ => HashFileFormat::HashSum,
     }
 }
 
-fn parse_hash_check_entry(line: &str) -> Option<HashFileEntry> {
+fn get_hash_file_format_name(hash_file_format: HashFileFormat) -> &'static str {
+    match hash_file_format {
+        HashFileFormat::HashCheck => HASH_CHECK_FORMAT_NAME,
+        HashFileFormat::HashSum => HASH_SUM_FORMAT_NAME,
+    }
+}
+
+fn get_hash_file_format_from_name(format_name: &str) -> Result<HashFileFormat> {
+    match format_name {
+        HASH_CHECK_FORMAT_NAME => Ok(HashFileFormat::HashCheck),
+        HASH_SUM_FORMAT_NAME => Ok(HashFileFormat::HashSum),
+        _ => Err(invalid_data(format!(
+            "Unsupported checksum file format: {}.",
+            format_name
+        ))),
+    }
+}
+
+#[cfg(not(windows))]
+fn get_host_name() -> Option<String> {
+    let mut buffer = [0u8; 256];
+    if unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) } != 0 {
+        return None;
+    }
+
+    let length = buffer.iter().position(|&byte| byte == 0)?;
+    String::from_utf8(buffer[..length].to_vec()).ok()
+}
+
+#[cfg(windows)]
+fn get_host_name() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+// Extension of a checksum file, ignoring the compression extension.
+fn get_uncompressed_extension(file_path: &Path) -> Option<&str> {
+    let file_path = match get_hash_file_compression(file_path) {
+        HashFileCompression::None => file_path,
+        _ => Path::new(file_path.file_stem()?),
+    };
+    file_path.extension()?.to_str()
+}
+
+fn get_decoder(file_path: &Path, file: File) -> Result<Box<dyn Read>> {
+    Ok(match get_hash_file_compression(file_path) {
+        HashFileCompression::None => Box::new(file),
+        HashFileCompression::Gzip => Box::new(MultiGzDecoder::new(file)),
+        HashFileCompression::Zstd => Box::new(zstd::Decoder::new(file)?),
+    })
+}
+
+// Content is written by `write`, through an encoder when `file_path` has a
+// compression extension.
+fn write_content<F: FnOnce(&mut dyn Write) -> Result<()>>(
+    file_path: &Path,
+    file: File,
+    write: F,
+) -> Result<()> {
+    let mut writer = BufWriter::new(file);
+    match get_hash_file_compression(file_path) {
+        HashFileCompression::None => write(&mut writer)?,
+        HashFileCompression::Gzip => {
+            let mut encoder = GzEncoder::new(writer, Compression::default());
+            write(&mut encoder)?;
+            writer = encoder.finish()?;
+        }
+        HashFileCompression::Zstd => {
+            let mut encoder = zstd::Encoder::new(writer, 0)?;
+            write(&mut encoder)?;
+            writer = encoder.finish()?;
+        }
+    }
+
+    writer.flush()
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn parse_header_line(line: &str) -> Option<(&str, &str)> {
+    let (name, value) = line.strip_prefix(HEADER_LINE_PREFIX)?.split_once(": ")?;
+    Some((name.trim(), value.trim()))
+}
+
+fn parse_hash_check_entry(line: &str) -> EntryParseResult {
     let parts: Vec<&str> = line.split('|').collect();
-    if parts.len() == 3 {
-        if parts[0].len() > MAX_PATH_SIZE {
-            panic!(
-                "File path length must be less than {} characters.",
-                MAX_PATH_SIZE + 1
-            );
+    if parts.len() != 3 {
+        return Err("expected `path|size|digest`".into());
+    }
+
+    check_path_length(parts[0])?;
+    let size = parts[1]
+        .parse::<u64>()
+        .map_err(|_| format!("invalid file size {:?}", parts[1]))?;
+    Ok(HashFileEntry {
+        file_path: parts[0].to_string(),
+        size: Some(size),
+        digest: parts[2].to_lowercase(),
+    })
+}
+
+fn check_path_length(file_path: &str) -> std::result::Result<(), String> {
+    if file_path.len() > MAX_PATH_SIZE {
+        return Err(format!(
+            "file path length must be less than {} characters",
+            MAX_PATH_SIZE + 1
+        ));
+    }
+
+    Ok(())
+}
+
+// Digests of hash functions with a variable digest length must all have the
+// length of the first one, which is kept in `digest_length` (in bits).
+fn check_digest(
+    digest: &str,
+    hash_type: HashType,
+    digest_length: &mut Option<usize>,
+) -> std::result::Result<(), String> {
+    if digest.is_empty() || !digest.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(format!("digest {:?} is not hexadecimal", digest));
+    }
+
+    let length = digest.len() * 4;
+    let expected_length = match digest_length {
+        Some(expected_length) => *expected_length,
+        None if crate::supports_digest_length(hash_type) => {
+            if !crate::is_valid_digest_length(hash_type, length) {
+                return Err(format!(
+                    "unsupported {} digest length: {} bits",
+                    <&str>::from(hash_type),
+                    length
+                ));
+            }
+
+            *digest_length = Some(length);
+            length
         }
+        None => crate::get_default_digest_length(hash_type),
+    };
+    if length != expected_length {
+        return Err(format!(
+            "{} digest length must be {} bits, found {}",
+            <&str>::from(hash_type),
+            expected_length,
+            length
+        ));
+    }
 
-        if parts[2].len() > MAX_HASH_SIZE {
-            panic!(
-                "Hash length must be less than {} characters.",
-                MAX_HASH_SIZE + 1
-            );
+    Ok(())
+}
+
+fn get_line_errors(message: &str, mut line_errors: Vec<String>) -> Error {
+    let line_error_count = line_errors.len();
+    line_errors.truncate(MAX_REPORTED_LINE_ERRORS);
+    if line_error_count > MAX_REPORTED_LINE_ERRORS {
+        line_errors.push(format!(
+            "and {} more",
+            line_error_count - MAX_REPORTED_LINE_ERRORS
+        ));
+    }
+
+    invalid_data(format!("{} ({}).", message, line_errors.join("; ")))
+}
+
+// BSD-style lines (e.g. `sha256sum --tag` or `xxhsum --tag`): `TYPE (file) = digest`
+fn parse_bsd_tag_entry(line: &str) -> Option<HashFileEntry> {
+    let (tag, rest) = line.split_once(" (")?;
+    let (file_path, digest) = rest.rsplit_once(") = ")?;
+    if tag.is_empty()
+        || tag.contains(' ')
+        || digest.is_empty()
+        || !digest
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+
+    Some(HashFileEntry {
+        file_path: file_path.to_string(),
+        size: None,
+        digest: strip_digest_prefix(digest).to_lowercase(),
+    })
+}
+
+// `xxhsum` prefixes XXH3 64-bit digests to tell them apart from XXH64 digests.
+fn strip_digest_prefix(digest: &str) -> &str {
+    match digest.get(..XXH3_DIGEST_PREFIX.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(XXH3_DIGEST_PREFIX) => {
+            &digest[XXH3_DIGEST_PREFIX.len()..]
         }
+        _ => digest,
+    }
+}
 
-        let size = parts[1].parse::<u64>().expect("Failed to parse file size");
-        Some(HashFileEntry {
-            file_path: parts[0].to_string(),
-            size: Some(size),
-            binary: true,
-            digest: parts[2].to_lowercase(),
-        })
-    } else {
-        None
+// `path CRC32`, where the path may contain spaces.
+fn parse_sfv_entry(line: &str) -> EntryParseResult {
+    let (file_path, digest) = line
+        .trim_end()
+        .rsplit_once(' ')
+        .ok_or_else(|| String::from("expected `path crc32`"))?;
+    let file_path = file_path.trim_end();
+    if file_path.is_empty() {
+        return Err("expected `path crc32`".into());
     }
+
+    check_path_length(file_path)?;
+    Ok(HashFileEntry {
+        file_path: file_path.to_string(),
+        size: None,
+        digest: digest.to_lowercase(),
+    })
 }
 
-fn parse_hash_sum_entry(line: &str) -> Option<HashFileEntry> {
-    match line.find(' ') {
-        Some(space_position) => {
-            let digest = &line[..space_position];
-            let file_path = &line[space_position + 2..];
-            let binary = line.as_bytes()[space_position + 1] as char == '*';
-            if file_path.len() > MAX_PATH_SIZE {
-                panic!(
-                    "File path length must be less than {} characters.",
-                    MAX_PATH_SIZE + 1
-                );
-            }
+fn parse_hash_sum_entry(line: &str) -> EntryParseResult {
+    if let Some(file_entry) = parse_bsd_tag_entry(line) {
+        check_path_length(&file_entry.file_path)?;
+        return Ok(file_entry);
+    }
 
-            Some(HashFileEntry {
-                file_path: file_path.to_string(),
-                size: None,
-                binary,
-                digest: digest.to_lowercase(),
-            })
+    let (digest, rest) = line
+        .split_once(' ')
+        .ok_or_else(|| String::from("expected `digest  path`"))?;
+    // Entries are always written in binary mode (`*`), both modes are read.
+    let file_path = match rest.get(1..) {
+        Some(file_path)
+            if !file_path.is_empty() && (rest.starts_with('*') || rest.starts_with(' ')) =>
+        {
+            file_path
         }
-        _ => None,
+        _ => return Err("expected `digest  path`".into()),
+    };
+    check_path_length(file_path)?;
+    Ok(HashFileEntry {
+        file_path: file_path.to_string(),
+        size: None,
+        digest: strip_digest_prefix(digest).to_lowercase(),
+    })
+}
+
+pub fn format_entry(
+    entry: &HashFileEntry,
+    hash_type: HashType,
+    hash_file_format: HashFileFormat,
+) -> String {
+    match hash_file_format {
+        HashFileFormat::HashCheck => format_hash_check_entry(entry),
+        HashFileFormat::HashSum => format_hash_sum_entry(entry, hash_type),
     }
 }
 
+// Paths starting with `#` are written relative to the current directory (e.g.
+// `./#file`), so that they aren't mistaken for header lines.
 fn format_hash_check_entry(entry: &HashFileEntry) -> String {
+    let path_prefix = if entry.file_path.starts_with(COMMENT_PREFIX) {
+        "./"
+    } else {
+        ""
+    };
     format!(
-        "{}|{}|{}\n",
+        "{}{}|{}|{}\n",
+        path_prefix,
         &entry.file_path,
         &entry.size.unwrap().to_string(),
         &entry.digest
     )
 }
 
-fn format_hash_sum_entry(entry: &HashFileEntry) -> String {
-    format!("{} *{}\n", &entry.digest, &entry.file_path)
+fn format_hash_sum_entry(entry: &HashFileEntry, hash_type: HashType) -> String {
+    // `xxhsum` only accepts two spaces between the digest and the file path.
+    match hash_type {
+        HashType::XXH3_64 => format!(
+            "{}{}  {}\n",
+            XXH3_DIGEST_PREFIX, &entry.digest, &entry.file_path
+        ),
+        HashType::XXH128 => format!("{}  {}\n", &entry.digest, &entry.file_path),
+        _ => format!("{} *{}\n", &entry.digest, &entry.file_path),
+    }
 }
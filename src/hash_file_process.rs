@@ -1,14 +1,27 @@
-use crate::block_hasher::HashProgress;
+use crate::file_hash::FileHash;
 use crate::file_tree::{FileTree, FileTreeProcessor};
-use crate::hash_file::{HashFile, HashFileEntry};
-use crate::{HashFileFormat, HashType};
+use crate::hash_file::{
+    HashFile, HashFileEntry, HashFileReader, ALGORITHM_HEADER_NAME, KEYED_HEADER_NAME,
+    SFV_EXTENSION,
+};
+use crate::ignore_filter::IgnoreFilter;
+use crate::path_set::PathSet;
+use crate::sample;
+use crate::signature::{self, PublicKey};
+use crate::HashProgress;
+use crate::{HashFileCompression, HashFileFormat, HashType, SampleSize};
 use cancellation::{CancellationToken, CancellationTokenSource};
 use crossbeam::channel::{select, unbounded, Sender};
 use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
-use std::fs;
-use std::path::{Path, PathBuf};
+use std::ffi::OsString;
+use std::fs::{self, Metadata};
+use std::io::Error;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use strum::IntoEnumIterator;
 
 static HASHCHECK_BASE_FILE_NAME: &str = "hshchk";
@@ -19,11 +32,15 @@ const DEFAULT_BYTES_PROCESSED_NOTIFICATION_BLOCK_SIZE: usize = 2_097_152;
 pub enum HashFileProcessType {
     Create,
     Verify,
+    Update,
+    Diff,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum HashFileProcessResult {
     Success,
+    // Sampled files were verified successfully, but other files weren't checked.
+    Sampled,
     Error,
     NoFilesProcessed,
     Canceled,
@@ -36,6 +53,9 @@ pub enum FileProcessState {
     Missing,
     IncorrectSize,
     IncorrectHash,
+    Added,
+    Removed,
+    Updated,
     Error(String),
 }
 
@@ -45,6 +65,21 @@ pub struct FileProcessEntry {
     pub state: FileProcessState,
 }
 
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HashFileProcessStats {
+    pub files_processed: u64,
+    pub bytes_processed: u64,
+    pub mismatches: u64,
+    pub missing: u64,
+    pub errors: u64,
+    pub warnings: u64,
+    // When sampling, `sampled` of `sample_population` entries were picked with
+    // `sample_seed`.
+    pub sampled: u64,
+    pub sample_population: u64,
+    pub sample_seed: Option<u64>,
+}
+
 #[derive(Default)]
 pub struct FileProgress {
     pub file_path: String,
@@ -56,17 +91,63 @@ pub struct FileProgress {
 pub struct HashFileProcessOptions<'a> {
     pub base_path: PathBuf,
     pub hash_file_format: Option<HashFileFormat>,
+    pub hash_file_compression: Option<HashFileCompression>,
     pub hash_type: Option<HashType>,
+    pub digest_length: Option<usize>,
+    pub key: Option<Vec<u8>>,
+    pub public_key: Option<PublicKey>,
+    pub header: Option<bool>,
+    // Verifies files while reading the checksum file instead of loading it first.
+    // Ignored when creating or updating, or when a file list is given. Header
+    // mismatches are only reported after the files are verified.
+    pub stream: Option<bool>,
+    // Opens files listed in the checksum file directly instead of walking the
+    // target directory. Only applies when verifying. Extra files aren't reported.
+    pub no_walk: Option<bool>,
+    // Writes a checksum file in every directory, covering its direct files only.
+    // Checksum files of all subdirectories are checked when verifying.
+    pub per_directory: Option<bool>,
+    // Verifies every checksum file found in the target directory and its
+    // subdirectories, including those created by other tools (e.g. `SHA256SUMS`,
+    // `*.md5` or `*.sfv`), against the files of its own directory.
+    pub verify_all: Option<bool>,
+    // Verifies a random subset of the checksum file entries, picked from the seed
+    // (random when not given). Only applies when verifying. Extra files aren't
+    // reported.
+    pub sample: Option<SampleSize>,
+    pub seed: Option<u64>,
+    pub process_type: Option<HashFileProcessType>,
     pub force_create: Option<bool>,
     pub report_extra: Option<bool>,
     pub size_only: Option<bool>,
     pub match_pattern: Option<&'a str>,
     pub ignore_pattern: Option<&'a str>,
+    pub match_absolute_path: Option<bool>,
+    pub exclude_patterns: Option<Vec<&'a str>>,
+    pub include_patterns: Option<Vec<&'a str>>,
+    pub use_gitignore: Option<bool>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub newer_than: Option<SystemTime>,
+    pub older_than: Option<SystemTime>,
+    pub file_list: Option<Vec<PathBuf>>,
 }
 
 pub struct HashFileProcessor {
     hash_file: HashFile,
+    updated_hash_file: HashFile,
     hash_type: HashType,
+    digest_length: Option<usize>,
+    key: Option<Vec<u8>>,
+    public_key: Option<PublicKey>,
+    header: bool,
+    stream: bool,
+    no_walk: bool,
+    per_directory: bool,
+    verify_all: bool,
+    sample: Option<SampleSize>,
+    directory_headers: HashMap<PathBuf, Vec<(String, String)>>,
+    seen_paths: Option<PathSet>,
     hash_file_format: Option<HashFileFormat>,
     process_type: HashFileProcessType,
     hash_file_path: PathBuf,
@@ -76,8 +157,16 @@ pub struct HashFileProcessor {
     report_extra: bool,
     match_regex: Option<Regex>,
     ignore_regex: Option<Regex>,
+    match_absolute_path: bool,
+    ignore_filter: IgnoreFilter,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    newer_than: Option<SystemTime>,
+    older_than: Option<SystemTime>,
+    file_list: Option<Vec<PathBuf>>,
     error_occurred: bool,
     files_processed: bool,
+    stats: HashFileProcessStats,
     bytes_processed_notification_block_size: usize,
     cancellation_token: Option<Arc<CancellationToken>>,
     internal_hash_progress_sender: Option<Sender<HashProgress>>,
@@ -93,23 +182,65 @@ impl HashFileProcessor {
         let mut process_type = HashFileProcessType::Create;
         let mut hash_type = options.hash_type.unwrap_or(HashType::SHA1);
         let mut hash_file_format = options.hash_file_format;
+        let mut hash_file_compression = options.hash_file_compression;
+        let mut per_directory = options.per_directory.unwrap_or_default();
+        let verify_all = options.verify_all.unwrap_or_default();
         let cano_base_path = fs::canonicalize(options.base_path).unwrap();
-        if !options.force_create.unwrap_or_default() {
-            if let Some((existing_hash_type, existing_hash_file_format)) =
-                get_existing_file_hash_type(&cano_base_path, hash_type)
+        let find_existing = match options.process_type {
+            // Without an explicit process type, the presence of a checksum file dictates the mode.
+            None => !options.force_create.unwrap_or_default(),
+            Some(requested_process_type) => {
+                process_type = requested_process_type;
+                requested_process_type != HashFileProcessType::Create
+            }
+        };
+        if verify_all {
+            process_type = HashFileProcessType::Verify;
+        } else if find_existing {
+            let mut existing_hash_file = get_existing_file_hash_type(&cano_base_path, hash_type);
+            if existing_hash_file.is_none() && options.process_type.is_some() {
+                // Without a checksum file at the root, checksum files of subdirectories
+                // are checked. This doesn't apply when the mode is dictated by the
+                // presence of a checksum file, so that a tree holding checksum files of
+                // other tools isn't mistaken for one to verify.
+                existing_hash_file =
+                    get_existing_subdirectory_file_hash_type(&cano_base_path, hash_type);
+                per_directory = per_directory || existing_hash_file.is_some();
+            }
+
+            if let Some((
+                existing_hash_type,
+                existing_hash_file_format,
+                existing_hash_file_compression,
+            )) = existing_hash_file
             {
                 hash_type = existing_hash_type;
                 hash_file_format = Some(existing_hash_file_format);
-                process_type = HashFileProcessType::Verify;
+                hash_file_compression = Some(existing_hash_file_compression);
+                if options.process_type.is_none() {
+                    process_type = HashFileProcessType::Verify;
+                }
             }
         }
 
-        let hash_file_name = match hash_file_format {
-            Some(HashFileFormat::HashSum) => get_hashsum_file_name(hash_type),
-            _ => get_hashcheck_file_name(hash_type),
+        let no_walk =
+            options.no_walk.unwrap_or_default() && process_type == HashFileProcessType::Verify;
+        // Sampling doesn't apply to listed files, nor when verifying all checksum files.
+        let sample = if process_type == HashFileProcessType::Verify
+            && !verify_all
+            && options.file_list.is_none()
+        {
+            options.sample
+        } else {
+            None
         };
-
-        let hash_file_path = cano_base_path.join(hash_file_name);
+        let seed = options.seed;
+        let sample_seed = sample.map(|_| seed.unwrap_or_else(get_random_seed));
+        let hash_file_path = cano_base_path.join(get_hash_file_name(
+            hash_type,
+            hash_file_format.unwrap_or(HashFileFormat::HashCheck),
+            hash_file_compression.unwrap_or(HashFileCompression::None),
+        ));
         let bin_path = env::current_exe().unwrap();
         let mut bin_file_name = PathBuf::from(bin_path.file_name().unwrap());
         let mut work_path = env::current_dir().unwrap();
@@ -119,20 +250,53 @@ impl HashFileProcessor {
             bin_file_name = PathBuf::new();
         }
 
+        let ignore_filter = IgnoreFilter::new(
+            &cano_base_path,
+            options.use_gitignore.unwrap_or_default(),
+            &options.exclude_patterns.unwrap_or_default(),
+            &options.include_patterns.unwrap_or_default(),
+        );
+
         HashFileProcessor {
             hash_file: HashFile::new(),
+            updated_hash_file: HashFile::new(),
             hash_type,
+            digest_length: options.digest_length,
+            key: options.key,
+            public_key: options.public_key,
+            header: options.header.unwrap_or_default(),
+            stream: options.stream.unwrap_or_default(),
+            no_walk,
+            per_directory,
+            verify_all,
+            sample,
+            directory_headers: HashMap::new(),
+            seen_paths: None,
             hash_file_format,
             process_type,
             hash_file_path,
             bin_file_name,
             base_path: cano_base_path,
             size_only: options.size_only.unwrap_or_default(),
-            report_extra: options.report_extra.unwrap_or_default(),
+            report_extra: (options.report_extra.unwrap_or_default()
+                || process_type == HashFileProcessType::Diff)
+                && !no_walk
+                && sample.is_none(),
             match_regex: options.match_pattern.map(|s| Regex::new(s).unwrap()),
             ignore_regex: options.ignore_pattern.map(|s| Regex::new(s).unwrap()),
+            match_absolute_path: options.match_absolute_path.unwrap_or_default(),
+            ignore_filter,
+            min_size: options.min_size,
+            max_size: options.max_size,
+            newer_than: options.newer_than,
+            older_than: options.older_than,
+            file_list: options.file_list,
             error_occurred: false,
             files_processed: false,
+            stats: HashFileProcessStats {
+                sample_seed,
+                ..Default::default()
+            },
             bytes_processed_notification_block_size:
                 DEFAULT_BYTES_PROCESSED_NOTIFICATION_BLOCK_SIZE,
             cancellation_token: None,
@@ -170,6 +334,15 @@ impl HashFileProcessor {
     pub fn get_process_type(&self) -> HashFileProcessType {
         self.process_type
     }
+    pub fn get_hash_type(&self) -> HashType {
+        self.hash_type
+    }
+    pub fn get_base_path(&self) -> &Path {
+        &self.base_path
+    }
+    pub fn get_stats(&self) -> &HashFileProcessStats {
+        &self.stats
+    }
     pub fn process(&mut self) -> HashFileProcessResult {
         let cts = CancellationTokenSource::new();
         let cancellation_token = cts.token();
@@ -189,6 +362,13 @@ impl HashFileProcessor {
     fn handle_error(&mut self, file_path: &Path, error_state: FileProcessState) {
         self.error_occurred = true;
         self.files_processed = true;
+        match error_state {
+            FileProcessState::IncorrectSize | FileProcessState::IncorrectHash => {
+                self.stats.mismatches += 1
+            }
+            FileProcessState::Missing => self.stats.missing += 1,
+            _ => self.stats.errors += 1,
+        }
         if let Some(sender) = &self.error_event {
             sender
                 .send(FileProcessEntry {
@@ -199,6 +379,7 @@ impl HashFileProcessor {
         }
     }
     fn handle_warning(&mut self, file_path: &Path, warning_state: FileProcessState) {
+        self.stats.warnings += 1;
         if let Some(sender) = &self.warning_event {
             sender
                 .send(FileProcessEntry {
@@ -208,14 +389,536 @@ impl HashFileProcessor {
                 .unwrap();
         }
     }
+    fn is_excluded_by_pattern(&self, relative_file_path: &str) -> bool {
+        // Patterns are matched against the path relative to the base path, using `/` as
+        // the separator, unless matching against the absolute path was requested.
+        let match_path = if self.match_absolute_path {
+            self.base_path
+                .join(relative_file_path)
+                .to_string_lossy()
+                .into_owned()
+        } else {
+            relative_file_path.replace(MAIN_SEPARATOR, "/")
+        };
+
+        if let Some(regex) = &self.match_regex {
+            if !regex.is_match(&match_path) {
+                return true;
+            }
+        }
+
+        if let Some(regex) = &self.ignore_regex {
+            if regex.is_match(&match_path) {
+                return true;
+            }
+        }
+
+        false
+    }
+    fn is_excluded_entry(&mut self, file_path: &str) -> bool {
+        if self.is_excluded_by_pattern(file_path) {
+            return true;
+        }
+
+        if let Some(file_size) = self.hash_file.get_entry(file_path).unwrap().size {
+            if self.is_excluded_by_size(file_size) {
+                return true;
+            }
+        }
+
+        self.ignore_filter
+            .is_ignored(&self.base_path.join(file_path), false)
+    }
+    // The entry of a file that can't be read is kept, so that an I/O error neither
+    // drops its checksum when updating nor reports it as missing.
+    fn handle_file_error(&mut self, relative_file_path: &Path, error: Error) {
+        self.handle_error(
+            relative_file_path,
+            FileProcessState::Error(error.to_string()),
+        );
+        self.keep_entry(relative_file_path.to_str().unwrap());
+    }
+    fn keep_entry(&mut self, file_path: &str) {
+        if let Some(file_entry) = self.hash_file.remove_entry(file_path) {
+            if self.process_type == HashFileProcessType::Update {
+                self.updated_hash_file.add_entry(file_entry);
+            }
+        }
+    }
+    fn is_excluded_by_size(&self, file_size: u64) -> bool {
+        self.min_size.is_some_and(|min_size| file_size < min_size)
+            || self.max_size.is_some_and(|max_size| file_size > max_size)
+    }
+    fn is_excluded_by_modified_time(&self, metadata: &Metadata) -> bool {
+        if self.newer_than.is_none() && self.older_than.is_none() {
+            return false;
+        }
+
+        match metadata.modified() {
+            Ok(modified) => {
+                self.newer_than
+                    .is_some_and(|newer_than| modified < newer_than)
+                    || self
+                        .older_than
+                        .is_some_and(|older_than| modified >= older_than)
+            }
+            _ => false,
+        }
+    }
+    fn process_file_list(
+        &mut self,
+        file_list: &[PathBuf],
+        cancellation_token: &Arc<CancellationToken>,
+    ) {
+        let mut listed_file_paths = HashSet::new();
+        for listed_file_path in file_list {
+            if cancellation_token.is_canceled() {
+                break;
+            }
+
+            // Relative paths are relative to the target directory (e.g. the output of
+            // `find . -print0` run from it).
+            let file_path = get_absolute_path(&self.base_path.join(listed_file_path));
+            if file_path.is_dir() {
+                continue;
+            }
+
+            let relative_file_path = match file_path.strip_prefix(&self.base_path) {
+                Ok(relative_file_path) => relative_file_path,
+                Err(_) => {
+                    self.handle_error(
+                        listed_file_path,
+                        FileProcessState::Error("File is outside of target directory.".into()),
+                    );
+                    continue;
+                }
+            };
+
+            if let Some(relative_file_path_str) = relative_file_path.to_str() {
+                listed_file_paths.insert(relative_file_path_str.to_string());
+                if !file_path.exists()
+                    && self.process_type != HashFileProcessType::Create
+                    && self.hash_file.get_entry(relative_file_path_str).is_some()
+                {
+                    continue; // Reported as missing
+                }
+            }
+
+            if !self.ignore_filter.is_ignored(&file_path, false) {
+                self.process_file(&file_path);
+            }
+        }
+
+        // Only listed files can be reported as missing. Entries of other files are
+        // kept as is when updating.
+        for file_path in self.hash_file.get_file_paths() {
+            if !listed_file_paths.contains(&file_path) {
+                self.keep_entry(&file_path);
+            }
+        }
+    }
+    fn is_streaming(&self) -> bool {
+        self.stream
+            && (self.process_type == HashFileProcessType::Verify
+                || self.process_type == HashFileProcessType::Diff)
+            && self.file_list.is_none()
+            && !self.per_directory
+            && self.sample.is_none()
+    }
+    // Verifies files listed in the checksum file without walking the target
+    // directory. Files are processed directory by directory.
+    fn process_hash_file_entries(&mut self, cancellation_token: &Arc<CancellationToken>) {
+        let mut file_paths = self.hash_file.get_file_paths();
+        file_paths.sort_unstable_by(|a, b| compare_by_directory(Path::new(a), Path::new(b)));
+        for relative_file_path_str in file_paths {
+            if cancellation_token.is_canceled() {
+                return;
+            }
+
+            self.process_entry(&relative_file_path_str);
+        }
+    }
+    // Verifies a random subset of the checksum file entries. Entries that aren't
+    // sampled are not reported as missing.
+    fn process_sample(
+        &mut self,
+        sample_size: SampleSize,
+        cancellation_token: &Arc<CancellationToken>,
+    ) {
+        let mut file_paths: Vec<String> = self
+            .hash_file
+            .get_file_paths()
+            .into_iter()
+            .filter(|file_path| !self.is_excluded_entry(file_path))
+            .collect();
+        // Entries are sorted first, as their order in the checksum file isn't kept.
+        file_paths.sort_unstable();
+        self.stats.sample_population = file_paths.len() as u64;
+        let sample_count = get_sample_count(sample_size, file_paths.len());
+        sample::sample(
+            &mut file_paths,
+            sample_count,
+            self.stats.sample_seed.unwrap(),
+        );
+        self.stats.sampled = file_paths.len() as u64;
+        let sampled_file_paths: HashSet<String> = file_paths.into_iter().collect();
+        self.hash_file
+            .retain(|file_path| sampled_file_paths.contains(file_path));
+        self.process_hash_file_entries(cancellation_token);
+    }
+    // Verifies the file of a checksum file entry, then removes the entry.
+    fn process_entry(&mut self, relative_file_path_str: &str) {
+        if !self.is_excluded_entry(relative_file_path_str) {
+            let file_path = self.base_path.join(relative_file_path_str);
+            if file_path.exists() {
+                self.process_file(&file_path);
+            } else {
+                self.handle_error(Path::new(relative_file_path_str), FileProcessState::Missing);
+            }
+        }
+
+        self.hash_file.remove_entry(relative_file_path_str);
+    }
+    // Verifies files as entries are read, so that only the current entry is held
+    // in memory. Extra files are then looked for in a separate pass, using the
+    // paths seen while reading.
+    fn process_hash_file_reader(
+        &mut self,
+        mut hash_file_reader: HashFileReader,
+        cancellation_token: &Arc<CancellationToken>,
+    ) {
+        let mut seen_paths = PathSet::new();
+        loop {
+            if cancellation_token.is_canceled() {
+                return;
+            }
+
+            let file_entry = match hash_file_reader.next_entry() {
+                Ok(Some(file_entry)) => file_entry,
+                Ok(None) => break,
+                Err(error) => {
+                    self.handle_hash_file_error(error.to_string());
+                    return;
+                }
+            };
+
+            let relative_file_path_str = file_entry.file_path.clone();
+            if self.report_extra {
+                seen_paths.insert(&relative_file_path_str);
+            }
+
+            if crate::supports_digest_length(self.hash_type) {
+                if let Some(digest_length) = hash_file_reader.get_digest_length() {
+                    self.digest_length = Some(digest_length);
+                }
+            }
+
+            self.hash_file.add_entry(file_entry);
+            self.process_entry(&relative_file_path_str);
+        }
+
+        if let Err(error) = hash_file_reader.finish() {
+            self.handle_hash_file_error(error.to_string());
+            return;
+        }
+
+        if self.report_extra {
+            seen_paths.seal();
+            self.seen_paths = Some(seen_paths);
+            let path = self.base_path.clone();
+            let mut file_tree = FileTree::new(self);
+            file_tree.traverse(&path, cancellation_token);
+        }
+    }
+    // Creates a checksum file with a header, for files of `base_path`.
+    fn new_hash_file(&self, base_path: &Path) -> HashFile {
+        let mut hash_file = HashFile::new();
+        if self.header {
+            hash_file.set_metadata(
+                self.hash_type,
+                self.hash_file_format.unwrap_or(HashFileFormat::HashCheck),
+                base_path,
+            );
+        }
+
+        if self.key.is_some() {
+            hash_file.set_header_value(
+                KEYED_HEADER_NAME,
+                crate::get_keyed_hash_name(self.hash_type),
+            );
+        }
+
+        hash_file
+    }
+    // The checksum file is only trusted once its signature is verified.
+    fn verify_signature(&self, hash_file_path: &Path) -> Result<(), String> {
+        let public_key = match &self.public_key {
+            Some(public_key) => public_key,
+            None => return Ok(()),
+        };
+        if !signature::get_signature_path(hash_file_path).is_file() {
+            return Err("Signature file not found.".into());
+        }
+
+        signature::verify_file(hash_file_path, public_key)
+            .map(|_| ())
+            .map_err(|error| error.to_string())
+    }
+    fn check_header(&self, hash_file: &HashFile) -> Result<(), String> {
+        let hash_type_name: &str = self.hash_type.into();
+        if let Some(algorithm) = hash_file.get_header_value(ALGORITHM_HEADER_NAME) {
+            if !algorithm.eq_ignore_ascii_case(hash_type_name) {
+                return Err(format!(
+                    "Checksum file algorithm ({}) doesn't match its file name ({}).",
+                    algorithm, hash_type_name
+                ));
+            }
+        }
+
+        match (hash_file.get_header_value(KEYED_HEADER_NAME), &self.key) {
+            (Some(keyed_hash_name), None) => Err(format!(
+                "Checksum file was created with a keyed hash function ({}). \
+                 A key is required to verify it.",
+                keyed_hash_name
+            )),
+            (None, Some(_)) => Err(
+                "A key was supplied but the checksum file was not created with a keyed hash function."
+                    .into(),
+            ),
+            _ => Ok(()),
+        }
+    }
+    fn load_directory_hash_file(&self, hash_file_path: &Path) -> Result<HashFile, String> {
+        self.verify_signature(hash_file_path)?;
+        let mut hash_file = HashFile::new();
+        hash_file
+            .load(hash_file_path, self.hash_type)
+            .map_err(|error| error.to_string())?;
+        self.check_header(&hash_file)?;
+        Ok(hash_file)
+    }
+    // Loads the checksum files of the target directory and its subdirectories.
+    // Entries are merged, with paths relative to the target directory.
+    fn load_directory_hash_files(&mut self) -> bool {
+        let hash_file_name = self.hash_file_path.file_name().unwrap().to_os_string();
+        let hash_file_paths = find_directory_hash_files(&self.base_path);
+        if hash_file_paths.is_empty() {
+            self.handle_hash_file_error("Checksum file not found.".into());
+            return false;
+        }
+
+        // Checksum files of different types, formats or compressions can't be checked
+        // together.
+        if let Some(hash_file_path) = hash_file_paths
+            .iter()
+            .find(|hash_file_path| hash_file_path.file_name() != Some(&hash_file_name))
+        {
+            let message = format!(
+                "Checksum file differs from {}.",
+                Path::new(&hash_file_name).display()
+            );
+            self.handle_error(
+                hash_file_path.strip_prefix(&self.base_path).unwrap(),
+                FileProcessState::Error(message),
+            );
+            return false;
+        }
+
+        for hash_file_path in hash_file_paths {
+            let directory = hash_file_path
+                .parent()
+                .unwrap()
+                .strip_prefix(&self.base_path)
+                .unwrap()
+                .to_path_buf();
+            let hash_file = match self.load_directory_hash_file(&hash_file_path) {
+                Ok(hash_file) => hash_file,
+                Err(message) => {
+                    self.handle_error(
+                        &directory.join(&hash_file_name),
+                        FileProcessState::Error(message),
+                    );
+                    return false;
+                }
+            };
+
+            if self.process_type == HashFileProcessType::Update {
+                self.directory_headers
+                    .insert(directory.clone(), hash_file.get_header().to_vec());
+            }
+
+            self.add_directory_entries(&directory, hash_file);
+        }
+
+        true
+    }
+    // Adds the entries of the checksum file of `directory`, with paths relative to
+    // the target directory.
+    fn add_directory_entries(&mut self, directory: &Path, hash_file: HashFile) {
+        for mut file_entry in hash_file.into_entries() {
+            file_entry.file_path = directory
+                .join(&file_entry.file_path)
+                .to_string_lossy()
+                .into_owned();
+            self.hash_file.add_entry(file_entry);
+        }
+    }
+    // Checksum files are loaded and verified one at a time, each with its own hash
+    // function type. Errors in a checksum file don't prevent checking the others.
+    fn process_all_hash_files(&mut self, cancellation_token: &Arc<CancellationToken>) {
+        for (hash_file_path, hash_type) in find_all_hash_files(&self.base_path) {
+            if cancellation_token.is_canceled() {
+                return;
+            }
+
+            let relative_hash_file_path = hash_file_path
+                .strip_prefix(&self.base_path)
+                .unwrap()
+                .to_path_buf();
+            self.hash_type = hash_type;
+            let hash_file = match self.load_directory_hash_file(&hash_file_path) {
+                Ok(hash_file) => hash_file,
+                Err(message) => {
+                    self.handle_error(&relative_hash_file_path, FileProcessState::Error(message));
+                    continue;
+                }
+            };
+
+            self.digest_length = hash_file
+                .get_digest_length()
+                .filter(|_| crate::supports_digest_length(hash_type));
+            self.add_directory_entries(relative_hash_file_path.parent().unwrap(), hash_file);
+            self.process_hash_file_entries(cancellation_token);
+        }
+    }
+    // Keeps the header of the checksum file being updated, if any.
+    fn new_directory_hash_file(&self, directory: &Path) -> HashFile {
+        match self.directory_headers.get(directory) {
+            Some(header) => {
+                let mut hash_file = HashFile::new();
+                for (name, value) in header {
+                    hash_file.set_header_value(name, value);
+                }
+
+                hash_file
+            }
+            None => self.new_hash_file(&self.base_path.join(directory)),
+        }
+    }
+    // Writes a checksum file in every directory having files. Checksum files of
+    // directories left without files are removed when updating.
+    fn save_per_directory(&self, hash_file: HashFile) {
+        let hash_file_name = self.hash_file_path.file_name().unwrap();
+        let mut directory_hash_files = BTreeMap::new();
+        for mut file_entry in hash_file.into_entries() {
+            let file_path = PathBuf::from(&file_entry.file_path);
+            let directory = file_path.parent().unwrap_or_else(|| Path::new(""));
+            file_entry.file_path = file_path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            directory_hash_files
+                .entry(directory.to_path_buf())
+                .or_insert_with(|| self.new_directory_hash_file(directory))
+                .add_entry(file_entry);
+        }
+
+        for (directory, directory_hash_file) in &directory_hash_files {
+            directory_hash_file.save(
+                &self.base_path.join(directory).join(hash_file_name),
+                self.hash_type,
+                self.hash_file_format.unwrap_or(HashFileFormat::HashCheck),
+            );
+        }
+
+        for directory in self.directory_headers.keys() {
+            if !directory_hash_files.contains_key(directory) {
+                let _ = fs::remove_file(self.base_path.join(directory).join(hash_file_name));
+            }
+        }
+    }
+    fn handle_hash_file_error(&mut self, message: String) {
+        let hash_file_path = self.hash_file_path.clone();
+        self.handle_error(
+            hash_file_path.strip_prefix(&self.base_path).unwrap(),
+            FileProcessState::Error(message),
+        );
+    }
     fn process_internal(
         &mut self,
         cancellation_token: Arc<CancellationToken>,
     ) -> HashFileProcessResult {
         self.cancellation_token = Some(cancellation_token.clone());
 
-        if self.process_type == HashFileProcessType::Verify {
-            self.hash_file.load(&self.hash_file_path);
+        if self.key.is_some() && !self.verify_all && !crate::supports_key(self.hash_type) {
+            let message = format!(
+                "{} does not support keyed hashing.",
+                <&str>::from(self.hash_type)
+            );
+            self.handle_hash_file_error(message);
+            return HashFileProcessResult::Error;
+        }
+
+        let mut hash_file_reader = None;
+        if self.process_type == HashFileProcessType::Create {
+            self.hash_file = self.new_hash_file(&self.base_path);
+        } else if !self.verify_all {
+            // When verifying all checksum files, they are loaded while processing.
+            if self.per_directory {
+                if !self.load_directory_hash_files() {
+                    return HashFileProcessResult::Error;
+                }
+            } else {
+                if !self.hash_file_path.is_file() {
+                    self.handle_hash_file_error("Checksum file not found.".into());
+                    return HashFileProcessResult::Error;
+                }
+
+                if let Err(message) = self.verify_signature(&self.hash_file_path) {
+                    self.handle_hash_file_error(message);
+                    return HashFileProcessResult::Error;
+                }
+
+                if self.is_streaming() {
+                    match HashFileReader::open(&self.hash_file_path, self.hash_type) {
+                        Ok(reader) => {
+                            for (name, value) in reader.get_header() {
+                                self.hash_file.set_header_value(name, value);
+                            }
+
+                            hash_file_reader = Some(reader);
+                        }
+                        Err(error) => {
+                            self.handle_hash_file_error(error.to_string());
+                            return HashFileProcessResult::Error;
+                        }
+                    }
+                } else if let Err(error) = self.hash_file.load(&self.hash_file_path, self.hash_type)
+                {
+                    self.handle_hash_file_error(error.to_string());
+                    return HashFileProcessResult::Error;
+                }
+
+                if let Err(message) = self.check_header(&self.hash_file) {
+                    self.handle_hash_file_error(message);
+                    return HashFileProcessResult::Error;
+                }
+
+                if self.process_type == HashFileProcessType::Update {
+                    for (name, value) in self.hash_file.get_header() {
+                        self.updated_hash_file.set_header_value(name, value);
+                    }
+                }
+            }
+
+            if crate::supports_digest_length(self.hash_type) {
+                // Checksum files may have been created with shortened or extended digests
+                // (e.g. `b2sum -l 256` or `b3sum --length 64`).
+                if let Some(digest_length) = self.hash_file.get_digest_length() {
+                    self.digest_length = Some(digest_length);
+                }
+            }
         }
 
         let mut progress_thread: Option<std::thread::JoinHandle<()>> = None;
@@ -259,11 +962,20 @@ impl HashFileProcessor {
             }));
         }
 
-        let path = self.base_path.clone();
-        let mut file_tree = FileTree::new(self);
-
-        if let Err(why) = file_tree.traverse(&path, &cancellation_token) {
-            panic!("Couldn't traverse {}: {}.", path.display(), why);
+        if self.verify_all {
+            self.process_all_hash_files(&cancellation_token);
+        } else if let Some(hash_file_reader) = hash_file_reader {
+            self.process_hash_file_reader(hash_file_reader, &cancellation_token);
+        } else if let Some(file_list) = self.file_list.take() {
+            self.process_file_list(&file_list, &cancellation_token);
+        } else if let Some(sample_size) = self.sample {
+            self.process_sample(sample_size, &cancellation_token);
+        } else if self.no_walk {
+            self.process_hash_file_entries(&cancellation_token);
+        } else {
+            let path = self.base_path.clone();
+            let mut file_tree = FileTree::new(self);
+            file_tree.traverse(&path, &cancellation_token);
         }
 
         if let Some(progress_sender) = self.internal_progress_sender.take() {
@@ -278,6 +990,19 @@ impl HashFileProcessor {
             return HashFileProcessResult::Canceled;
         }
 
+        // Missing files are reported even when other files couldn't be processed.
+        if self.process_type != HashFileProcessType::Create
+            && self.process_type != HashFileProcessType::Update
+        {
+            for file_path in self.hash_file.get_file_paths() {
+                if self.is_excluded_entry(&file_path) {
+                    continue;
+                }
+
+                self.handle_error(Path::new(&file_path), FileProcessState::Missing);
+            }
+        }
+
         if self.error_occurred {
             return HashFileProcessResult::Error;
         } else if self.process_type == HashFileProcessType::Create {
@@ -285,33 +1010,47 @@ impl HashFileProcessor {
                 return HashFileProcessResult::NoFilesProcessed;
             }
 
-            self.hash_file.save(
-                &self.hash_file_path,
-                self.hash_file_format.unwrap_or(HashFileFormat::HashCheck),
-            );
-        } else if self.process_type == HashFileProcessType::Verify && !self.hash_file.is_empty() {
+            if self.per_directory {
+                let hash_file = std::mem::replace(&mut self.hash_file, HashFile::new());
+                self.save_per_directory(hash_file);
+            } else {
+                self.hash_file.save(
+                    &self.hash_file_path,
+                    self.hash_type,
+                    self.hash_file_format.unwrap_or(HashFileFormat::HashCheck),
+                );
+            }
+        } else if self.process_type == HashFileProcessType::Update {
             for file_path in self.hash_file.get_file_paths() {
-                if let Some(regex) = &self.match_regex {
-                    if !regex.is_match(&file_path) {
-                        continue;
-                    }
+                if !self.is_excluded_entry(&file_path) {
+                    self.handle_warning(Path::new(&file_path), FileProcessState::Removed);
+                    continue;
                 }
 
-                if let Some(regex) = &self.ignore_regex {
-                    if regex.is_match(&file_path) {
-                        continue;
-                    }
-                }
+                self.keep_entry(&file_path);
+            }
 
-                self.handle_error(Path::new(&file_path), FileProcessState::Missing);
+            if self.updated_hash_file.is_empty() {
+                return HashFileProcessResult::NoFilesProcessed;
             }
 
-            if self.error_occurred {
-                return HashFileProcessResult::Error;
+            if self.per_directory {
+                let hash_file = std::mem::replace(&mut self.updated_hash_file, HashFile::new());
+                self.save_per_directory(hash_file);
+            } else {
+                self.updated_hash_file.save(
+                    &self.hash_file_path,
+                    self.hash_type,
+                    self.hash_file_format.unwrap_or(HashFileFormat::HashCheck),
+                );
             }
+
+            self.files_processed = true;
         }
 
-        if self.files_processed {
+        if self.files_processed && self.sample.is_some() {
+            HashFileProcessResult::Sampled
+        } else if self.files_processed {
             HashFileProcessResult::Success
         } else {
             HashFileProcessResult::NoFilesProcessed
@@ -321,55 +1060,79 @@ impl HashFileProcessor {
 
 impl FileTreeProcessor for HashFileProcessor {
     fn process_file(&mut self, file_path: &Path) {
-        if file_path == self.hash_file_path {
+        if file_path == self.hash_file_path
+            || (self.per_directory && file_path.file_name() == self.hash_file_path.file_name())
+        {
             return; // Skip current hash file
         }
 
-        let file_path_str = match file_path.to_str() {
-            Some(file_path_str) => file_path_str,
-            None => {
-                self.handle_warning(file_path, FileProcessState::InvalidUnicodeFileName);
-                return;
-            }
-        };
+        if file_path.to_str().is_none() {
+            self.handle_warning(file_path, FileProcessState::InvalidUnicodeFileName);
+            return;
+        }
 
-        if let Some(regex) = &self.match_regex {
-            if !regex.is_match(file_path_str) {
-                return;
-            }
+        let relative_file_path = file_path.strip_prefix(&self.base_path).unwrap();
+        let relative_file_path_str = relative_file_path.to_str().unwrap();
+        if self.is_excluded_by_pattern(relative_file_path_str) {
+            return;
         }
 
-        if let Some(regex) = &self.ignore_regex {
-            if regex.is_match(file_path_str) {
+        let metadata = match file_path.metadata() {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                self.handle_file_error(relative_file_path, error);
                 return;
             }
+        };
+
+        let file_size = metadata.len();
+        // Recorded sizes take precedence, so that files truncated or grown past the size
+        // limits are still verified.
+        let recorded_file_size = self
+            .hash_file
+            .get_entry(relative_file_path_str)
+            .and_then(|file_entry| file_entry.size);
+        if self.is_excluded_by_size(recorded_file_size.unwrap_or(file_size))
+            || self.is_excluded_by_modified_time(&metadata)
+        {
+            // Excluded files must not be reported as missing when verifying.
+            self.keep_entry(relative_file_path_str);
+            return;
         }
 
-        let relative_file_path = file_path.strip_prefix(&self.base_path).unwrap();
-        let relative_file_path_str = relative_file_path.to_str().unwrap();
-        let file_size: u64;
-        match file_path.metadata() {
-            Ok(metadata) => file_size = metadata.len(),
-            Err(error) => {
-                self.handle_error(
-                    relative_file_path,
-                    FileProcessState::Error(error.to_string()),
-                );
-                return;
+        if let Some(seen_paths) = &self.seen_paths {
+            // Listed files were verified while reading the checksum file.
+            if !seen_paths.contains(relative_file_path_str)
+                && relative_file_path != self.bin_file_name
+            {
+                self.handle_warning(relative_file_path, FileProcessState::Extra);
             }
+
+            return;
         }
 
         let hash_file_entry = self.hash_file.get_entry(relative_file_path_str);
         if let Some(file_entry) = hash_file_entry {
             if let Some(file_entry_size) = file_entry.size {
-                if file_size != file_entry_size {
+                if self.process_type == HashFileProcessType::Update {
+                    if file_size == file_entry_size {
+                        self.keep_entry(relative_file_path_str);
+                        return;
+                    }
+                } else if file_size != file_entry_size {
                     self.handle_error(relative_file_path, FileProcessState::IncorrectSize);
+                    self.hash_file.remove_entry(relative_file_path_str);
                     return;
                 }
+            } else if self.process_type == HashFileProcessType::Update {
+                self.keep_entry(relative_file_path_str);
+                return;
             }
         } else if relative_file_path == self.bin_file_name {
             return; // Skip app binary file
-        } else if self.process_type == HashFileProcessType::Verify {
+        } else if self.process_type == HashFileProcessType::Verify
+            || self.process_type == HashFileProcessType::Diff
+        {
             if self.report_extra {
                 self.handle_warning(relative_file_path, FileProcessState::Extra);
             }
@@ -378,8 +1141,24 @@ impl FileTreeProcessor for HashFileProcessor {
         }
 
         let mut digest = String::from("");
-        if !(self.size_only && self.process_type == HashFileProcessType::Verify) {
-            let mut file_hasher = crate::get_file_hasher(self.hash_type, file_path);
+        let mut bytes_processed = 0;
+        if !(self.size_only
+            && (self.process_type == HashFileProcessType::Verify
+                || self.process_type == HashFileProcessType::Diff))
+        {
+            let file_hash = match FileHash::open(
+                self.hash_type,
+                self.digest_length,
+                self.key.as_deref(),
+                file_path,
+            ) {
+                Ok(file_hash) => file_hash,
+                Err(error) => {
+                    self.handle_file_error(relative_file_path, error);
+                    return;
+                }
+            };
+            let mut hash_progress_sender = None;
             if let Some(progress_sender) = &self.internal_progress_sender {
                 progress_sender
                     .send(FileProgress {
@@ -388,14 +1167,11 @@ impl FileTreeProcessor for HashFileProcessor {
                         bytes_processed: 0,
                     })
                     .unwrap();
-                if let Some(hash_progress_sender) = &self.internal_hash_progress_sender {
-                    file_hasher.set_bytes_processed_event_sender(hash_progress_sender.clone());
-                }
+                hash_progress_sender = self.internal_hash_progress_sender.clone();
             }
 
             let cancellation_token = self.cancellation_token.as_ref().unwrap();
-            file_hasher.compute(cancellation_token.clone());
-            digest = file_hasher.digest();
+            let hash_result = file_hash.compute(cancellation_token.clone(), hash_progress_sender);
 
             if let Some(progress_sender) = &self.internal_progress_sender {
                 progress_sender
@@ -410,16 +1186,37 @@ impl FileTreeProcessor for HashFileProcessor {
             if cancellation_token.is_canceled() {
                 return;
             }
+
+            match hash_result {
+                Ok(file_digest) => {
+                    digest = file_digest.hex;
+                    bytes_processed = file_digest.bytes_processed;
+                }
+                Err(error) => {
+                    self.handle_file_error(relative_file_path, error);
+                    return;
+                }
+            }
         }
 
         if self.process_type == HashFileProcessType::Create {
             self.hash_file.add_entry(HashFileEntry {
                 file_path: relative_file_path_str.to_string(),
                 size: Some(file_size),
-                binary: true,
                 digest,
             });
-        } else if self.process_type == HashFileProcessType::Verify {
+        } else if self.process_type == HashFileProcessType::Update {
+            let update_state = match self.hash_file.remove_entry(relative_file_path_str) {
+                Some(_) => FileProcessState::Updated,
+                None => FileProcessState::Added,
+            };
+            self.updated_hash_file.add_entry(HashFileEntry {
+                file_path: relative_file_path_str.to_string(),
+                size: Some(file_size),
+                digest,
+            });
+            self.handle_warning(relative_file_path, update_state);
+        } else {
             if let Some(file_entry) = hash_file_entry {
                 if !self.size_only && digest != file_entry.digest {
                     self.handle_error(relative_file_path, FileProcessState::IncorrectHash);
@@ -430,6 +1227,213 @@ impl FileTreeProcessor for HashFileProcessor {
         }
 
         self.files_processed = true;
+        self.stats.files_processed += 1;
+        self.stats.bytes_processed += bytes_processed;
+    }
+    fn process_error(&mut self, path: &Path, error: Error) {
+        let relative_path = path.strip_prefix(&self.base_path).unwrap_or(path);
+        self.handle_error(relative_path, FileProcessState::Error(error.to_string()));
+    }
+    fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        self.ignore_filter.is_ignored(path, is_dir)
+    }
+}
+
+// Resolves `.` and `..` components and symbolic links of existing paths
+// without requiring the path itself to exist.
+fn get_absolute_path(path: &Path) -> PathBuf {
+    if let Ok(cano_path) = fs::canonicalize(path) {
+        return cano_path;
+    }
+
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => get_absolute_path(parent).join(file_name),
+        _ => path.to_path_buf(),
+    }
+}
+
+// Visits `base_path` and its subdirectories in sorted order, along with the names
+// of their files, until `visit` returns `false`.
+fn visit_directories<F: FnMut(&Path, &HashSet<OsString>) -> bool>(base_path: &Path, mut visit: F) {
+    let mut directories = vec![base_path.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(_) => continue, // Reported when processing files
+        };
+        let mut file_names = HashSet::new();
+        let mut subdirectories = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                subdirectories.push(path);
+            } else {
+                file_names.insert(entry.file_name());
+            }
+        }
+
+        if !visit(&directory, &file_names) {
+            return;
+        }
+
+        subdirectories.sort_unstable_by(|a, b| b.cmp(a));
+        directories.extend(subdirectories);
+    }
+}
+
+// Names of the checksum files created by `hshchk`, of any type, format or
+// compression.
+fn get_hash_file_names() -> HashSet<PathBuf> {
+    let mut hash_file_names = HashSet::new();
+    for hash_type in HashType::iter() {
+        for hash_file_compression in HashFileCompression::iter() {
+            for hash_file_format in HashFileFormat::iter() {
+                hash_file_names.insert(get_hash_file_name(
+                    hash_type,
+                    hash_file_format,
+                    hash_file_compression,
+                ));
+            }
+        }
+    }
+
+    hash_file_names
+}
+
+// Finds the per-directory checksum files of `base_path` and its subdirectories,
+// whatever their type, format or compression.
+fn find_directory_hash_files(base_path: &Path) -> Vec<PathBuf> {
+    let hash_file_names = get_hash_file_names();
+    let mut hash_file_paths = Vec::new();
+    visit_directories(base_path, |directory, file_names| {
+        let mut file_names: Vec<&OsString> = file_names
+            .iter()
+            .filter(|file_name| hash_file_names.contains(Path::new(file_name)))
+            .collect();
+        file_names.sort_unstable();
+        for file_name in file_names {
+            hash_file_paths.push(directory.join(file_name));
+        }
+
+        true
+    });
+    hash_file_paths
+}
+
+// Recognizes checksum files by name: `hshchk.<type>` and `<TYPE>SUMS`, as well as
+// `*.<type>` (e.g. `file.iso.sha256`) and `*.sfv`, possibly compressed.
+fn get_hash_file_type(file_name: &Path) -> Option<HashType> {
+    let file_name = match crate::get_hash_file_compression(file_name) {
+        HashFileCompression::None => file_name,
+        _ => Path::new(file_name.file_stem()?),
+    };
+    if let Some(hash_type_name) = file_name.to_str()?.strip_suffix(HASHSUM_SUFFIX) {
+        return hash_type_name.parse().ok();
+    }
+
+    let extension = file_name.extension()?.to_str()?;
+    if extension.eq_ignore_ascii_case(SFV_EXTENSION) {
+        return Some(HashType::CRC32);
+    }
+
+    extension.to_uppercase().parse().ok()
+}
+
+fn find_all_hash_files(base_path: &Path) -> Vec<(PathBuf, HashType)> {
+    let mut hash_files = Vec::new();
+    visit_directories(base_path, |directory, file_names| {
+        let mut file_names: Vec<&OsString> = file_names.iter().collect();
+        file_names.sort_unstable();
+        for file_name in file_names {
+            if let Some(hash_type) = get_hash_file_type(Path::new(file_name)) {
+                hash_files.push((directory.join(file_name), hash_type));
+            }
+        }
+
+        true
+    });
+    hash_files
+}
+
+// Looks for per-directory checksum files when there's none in `base_path`. The
+// checksum file found first dictates the hash function type and format.
+fn get_existing_subdirectory_file_hash_type(
+    base_path: &Path,
+    desired_hash_type: HashType,
+) -> Option<(HashType, HashFileFormat, HashFileCompression)> {
+    let hash_file_names = get_hash_file_names();
+    let mut existing_hash_file = None;
+    visit_directories(base_path, |directory, file_names| {
+        if directory != base_path
+            && file_names
+                .iter()
+                .any(|file_name| hash_file_names.contains(Path::new(file_name)))
+        {
+            existing_hash_file = get_existing_file_hash_type(directory, desired_hash_type);
+        }
+
+        existing_hash_file.is_none()
+    });
+    existing_hash_file
+}
+
+// At least one entry is sampled, unless there are none.
+fn get_sample_count(sample_size: SampleSize, entry_count: usize) -> usize {
+    let sample_count = match sample_size {
+        SampleSize::Count(count) => count.min(entry_count as u64) as usize,
+        SampleSize::Percent(percent) => (entry_count as f64 * percent / 100.0).ceil() as usize,
+    };
+
+    sample_count.clamp(1, entry_count.max(1))
+}
+
+fn get_random_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    sample::SplitMix64::new(nanos ^ u64::from(std::process::id())).next_u64()
+}
+
+// Orders paths by parent directory, so that files of a directory are grouped
+// and come before files of its subdirectories.
+fn compare_by_directory(a: &Path, b: &Path) -> Ordering {
+    a.parent()
+        .cmp(&b.parent())
+        .then_with(|| a.file_name().cmp(&b.file_name()))
+}
+
+// Returns the path of the checksum file in `base_path`, looking for one of
+// `desired_hash_type` first.
+pub fn find_hash_file(base_path: &Path, desired_hash_type: HashType) -> Option<PathBuf> {
+    get_existing_file_hash_type(base_path, desired_hash_type).map(
+        |(hash_type, hash_file_format, hash_file_compression)| {
+            base_path.join(get_hash_file_name(
+                hash_type,
+                hash_file_format,
+                hash_file_compression,
+            ))
+        },
+    )
+}
+
+fn get_hash_file_name(
+    hash_type: HashType,
+    hash_file_format: HashFileFormat,
+    hash_file_compression: HashFileCompression,
+) -> PathBuf {
+    let hash_file_name = match hash_file_format {
+        HashFileFormat::HashSum => get_hashsum_file_name(hash_type),
+        HashFileFormat::HashCheck => get_hashcheck_file_name(hash_type),
+    };
+    match crate::get_hash_file_compression_extension(hash_file_compression) {
+        Some(extension) => {
+            let mut file_name = hash_file_name.into_os_string();
+            file_name.push(".");
+            file_name.push(extension);
+            PathBuf::from(file_name)
+        }
+        None => hash_file_name,
     }
 }
 
@@ -446,16 +1450,24 @@ fn get_hashsum_file_name(hash_type: HashType) -> PathBuf {
     hash_file.to_path_buf()
 }
 
-fn hash_file_exists(hash_file_path: &mut PathBuf, hash_type: HashType) -> Option<HashFileFormat> {
-    hash_file_path.push(get_hashcheck_file_name(hash_type));
-    if hash_file_path.is_file() {
-        return Some(HashFileFormat::HashCheck);
-    }
+// Uncompressed checksum files are looked for first.
+fn hash_file_exists(
+    hash_file_path: &mut PathBuf,
+    hash_type: HashType,
+) -> Option<(HashFileFormat, HashFileCompression)> {
+    for hash_file_compression in HashFileCompression::iter() {
+        for hash_file_format in &[HashFileFormat::HashCheck, HashFileFormat::HashSum] {
+            hash_file_path.push(get_hash_file_name(
+                hash_type,
+                *hash_file_format,
+                hash_file_compression,
+            ));
+            if hash_file_path.is_file() {
+                return Some((*hash_file_format, hash_file_compression));
+            }
 
-    hash_file_path.pop();
-    hash_file_path.push(get_hashsum_file_name(hash_type));
-    if hash_file_path.is_file() {
-        return Some(HashFileFormat::HashSum);
+            hash_file_path.pop();
+        }
     }
 
     None
@@ -464,19 +1476,20 @@ fn hash_file_exists(hash_file_path: &mut PathBuf, hash_type: HashType) -> Option
 fn get_existing_file_hash_type(
     base_path: &Path,
     desired_hash_type: HashType,
-) -> Option<(HashType, HashFileFormat)> {
+) -> Option<(HashType, HashFileFormat, HashFileCompression)> {
     let mut hash_file_path = PathBuf::from(base_path);
 
-    if let Some(hash_file_format) = hash_file_exists(&mut hash_file_path, desired_hash_type) {
-        return Some((desired_hash_type, hash_file_format));
+    if let Some((hash_file_format, hash_file_compression)) =
+        hash_file_exists(&mut hash_file_path, desired_hash_type)
+    {
+        return Some((desired_hash_type, hash_file_format, hash_file_compression));
     } else {
-        hash_file_path.pop();
         for hash_type in HashType::iter() {
-            if let Some(hash_file_format) = hash_file_exists(&mut hash_file_path, hash_type) {
-                return Some((hash_type, hash_file_format));
+            if let Some((hash_file_format, hash_file_compression)) =
+                hash_file_exists(&mut hash_file_path, hash_type)
+            {
+                return Some((hash_type, hash_file_format, hash_file_compression));
             }
-
-            hash_file_path.pop();
         }
     }
 
@@ -1,134 +1,759 @@
-use cancellation::CancellationTokenSource;
-use clap::{crate_description, crate_name, crate_version, App, AppSettings, Arg};
-use std::io::{Error, ErrorKind};
-use std::path::PathBuf;
+use cancellation::{CancellationToken, CancellationTokenSource};
+use clap::{
+    crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches, SubCommand,
+};
+use std::fmt;
+use std::fs::File;
+use std::io::{stdin, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use hshchk::hash_file_process::{HashFileProcessOptions, HashFileProcessResult, HashFileProcessor};
+use hshchk::config::{self, Config};
+use hshchk::hash_file_process::{
+    self, HashFileProcessOptions, HashFileProcessResult, HashFileProcessType, HashFileProcessor,
+};
+use hshchk::report_log::ReportLog;
+use hshchk::signature::{self, PublicKey, SecretKey};
 use hshchk::ui;
+use hshchk::HashType;
 
-fn run() -> Result<(), Box<dyn (::std::error::Error)>> {
+// Exit codes, also documented in the help message and in README.md.
+const EXIT_FAILURE: i32 = 1;
+const EXIT_MISMATCH: i32 = 2;
+const EXIT_MISSING: i32 = 3;
+const EXIT_IO_ERROR: i32 = 4;
+const EXIT_NO_FILES_PROCESSED: i32 = 5;
+const EXIT_USAGE: i32 = 64;
+const EXIT_CONFIG: i32 = 78;
+const EXIT_CANCELED: i32 = 130;
+
+const KEY_ENV_VAR: &str = "HSHCHK_KEY";
+
+const EXIT_CODES_HELP: &str = "EXIT CODES:
+    0      Success (also when only a sample of the files was verified)
+    1      Other failure
+    2      Files with incorrect size or hash (takes precedence over other failures)
+    3      Missing files only
+    4      I/O errors (e.g. unreadable files, missing directory or checksum file)
+    5      No files were processed
+    64     Usage error
+    78     Invalid configuration file or environment variable
+    130    Canceled";
+
+#[derive(Debug)]
+struct ExitError {
+    code: i32,
+    message: String,
+}
+
+impl ExitError {
+    fn new(code: i32, message: &str) -> Self {
+        ExitError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ExitError {}
+
+fn get_exit_code(error: &(dyn std::error::Error + 'static)) -> i32 {
+    if let Some(exit_error) = error.downcast_ref::<ExitError>() {
+        exit_error.code
+    } else if let Some(clap_error) = error.downcast_ref::<clap::Error>() {
+        match clap_error.kind {
+            // The exit code should not indicate an error for --help / --version
+            clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => 0,
+            _ => EXIT_USAGE,
+        }
+    } else if let Some(io_error) = error.downcast_ref::<Error>() {
+        match io_error.kind() {
+            ErrorKind::Interrupted => EXIT_CANCELED,
+            _ => EXIT_IO_ERROR,
+        }
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+fn type_arg() -> Arg<'static, 'static> {
+    Arg::with_name("type")
+        .short("t")
+        .long("type")
+        .takes_value(true)
+        .value_name("type")
+        .possible_values(&hshchk::get_hash_types())
+        .case_insensitive(true)
+        .help("Hash function type")
+}
+
+fn length_arg() -> Arg<'static, 'static> {
+    Arg::with_name("length")
+        .short("l")
+        .long("length")
+        .takes_value(true)
+        .value_name("bits")
+        .validator(|length| {
+            length
+                .parse::<usize>()
+                .map(|_| ())
+                .map_err(|_| format!("Invalid digest length: {}.", length))
+        })
+        .help(
+            "Digest length in bits, a multiple of 8 (BLAKE2B, BLAKE2S and BLAKE3 only). \
+             When verifying, the length is inferred from the checksum file.",
+        )
+}
+
+// Validates the digest length against the hash type it applies to.
+fn get_digest_length(
+    matches: &ArgMatches,
+    hash_type: HashType,
+) -> Result<Option<usize>, ExitError> {
+    match matches
+        .value_of("length")
+        .map(|length| length.parse().unwrap())
+    {
+        Some(digest_length) if !hshchk::is_valid_digest_length(hash_type, digest_length) => {
+            Err(ExitError::new(
+                EXIT_USAGE,
+                &format!(
+                    "Unsupported digest length for {:?}: {}.",
+                    hash_type, digest_length
+                ),
+            ))
+        }
+        digest_length => Ok(digest_length),
+    }
+}
+
+fn key_file_arg() -> Arg<'static, 'static> {
+    Arg::with_name("key-file")
+        .long("key-file")
+        .takes_value(true)
+        .value_name("file")
+        .help(
+            "Use the keyed mode of the hash function (BLAKE2B, BLAKE2S, BLAKE3, or HMAC with \
+             SHA256) with the key read from specified file. The key can also be given in \
+             hexadecimal in the HSHCHK_KEY environment variable.",
+        )
+}
+
+// Keys are not read from configuration files so that they don't end up next to
+// the checksum files they protect.
+fn get_key(matches: &ArgMatches) -> Result<Option<Vec<u8>>, Box<dyn ::std::error::Error>> {
+    if let Some(key_file_path) = matches.value_of("key-file") {
+        return Ok(Some(std::fs::read(key_file_path)?));
+    }
+
+    match std::env::var(KEY_ENV_VAR) {
+        Ok(key) => hex::decode(key.trim()).map(Some).map_err(|_| {
+            Box::new(ExitError::new(
+                EXIT_CONFIG,
+                &format!(
+                    "Invalid value for {}: expected a hexadecimal key.",
+                    KEY_ENV_VAR
+                ),
+            )) as Box<dyn ::std::error::Error>
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+// Validates the key against the hash type it applies to.
+fn check_key(key: &Option<Vec<u8>>, hash_type: HashType) -> Result<(), ExitError> {
+    if key.is_some() && !hshchk::supports_key(hash_type) {
+        return Err(ExitError::new(
+            EXIT_USAGE,
+            &format!("{:?} does not support keyed hashing.", hash_type),
+        ));
+    }
+
+    Ok(())
+}
+
+fn sum_arg() -> Arg<'static, 'static> {
+    Arg::with_name("sum")
+        .short("u")
+        .long("sum")
+        .help("Use hash sum (e.g. sha1sum) file format")
+}
+
+fn directory_arg() -> Arg<'static, 'static> {
+    Arg::with_name("directory")
+        .required(true)
+        .help("Target directory")
+}
+
+// Boolean options that can be set in configuration files, along with the flags
+// turning them off from the command line.
+const FLAG_NEGATIONS: &[(&str, &str, &str)] = &[
+    (
+        "sum",
+        "no-sum",
+        "Use hshchk file format, overriding configuration files",
+    ),
+    (
+        "size",
+        "no-size",
+        "Check file hashes, overriding configuration files",
+    ),
+    (
+        "extra",
+        "no-extra",
+        "Don't report extra files, overriding configuration files",
+    ),
+    (
+        "silent",
+        "no-silent",
+        "Output to stdout, overriding configuration files",
+    ),
+    (
+        "match-absolute",
+        "no-match-absolute",
+        "Match regex patterns against relative file paths, overriding configuration files",
+    ),
+    (
+        "gitignore",
+        "no-gitignore",
+        "Don't honor .gitignore files, overriding configuration files",
+    ),
+    (
+        "header",
+        "no-header",
+        "Don't write a header, overriding configuration files",
+    ),
+    (
+        "stream",
+        "no-stream",
+        "Load the checksum file in memory, overriding configuration files",
+    ),
+    (
+        "no-walk",
+        "walk",
+        "Walk the target directory, overriding configuration files",
+    ),
+    (
+        "per-directory",
+        "no-per-directory",
+        "Don't use per-directory checksum files, overriding configuration files",
+    ),
+];
+
+fn flag_negation_args() -> Vec<Arg<'static, 'static>> {
+    FLAG_NEGATIONS
+        .iter()
+        .map(|(_, negation, help)| Arg::with_name(negation).long(negation).help(help))
+        .collect()
+}
+
+fn process_args() -> Vec<Arg<'static, 'static>> {
+    let mut args = vec![
+        type_arg(),
+        length_arg(),
+        Arg::with_name("size")
+            .short("f")
+            .long("size")
+            .help("Check file size only"),
+        Arg::with_name("extra")
+            .short("r")
+            .long("extra")
+            .help("Report extra files"),
+        Arg::with_name("silent")
+            .short("s")
+            .long("silent")
+            .help("Don't output to stdout"),
+        Arg::with_name("match")
+            .short("m")
+            .long("match")
+            .takes_value(true)
+            .value_name("pattern")
+            .help("Process files that matches regex pattern"),
+        Arg::with_name("ignore")
+            .short("i")
+            .long("ignore")
+            .takes_value(true)
+            .value_name("pattern")
+            .help("Ignore files that matches regex pattern"),
+        Arg::with_name("match-absolute")
+            .long("match-absolute")
+            .help(
+                "Match regex patterns against absolute file paths \
+                 instead of paths relative to target directory",
+            ),
+        Arg::with_name("exclude")
+            .long("exclude")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("glob")
+            .help("Exclude files that match glob pattern (gitignore syntax)"),
+        Arg::with_name("include")
+            .long("include")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("glob")
+            .help("Only process files that match glob pattern (gitignore syntax)"),
+        Arg::with_name("gitignore")
+            .long("gitignore")
+            .help("Honor .gitignore files in addition to .hshchkignore files"),
+        Arg::with_name("min-size")
+            .long("min-size")
+            .takes_value(true)
+            .value_name("size")
+            .validator(|size| hshchk::get_size_from_str(&size).map(|_| ()))
+            .help("Only process files of at least specified size (e.g. 100K, 1G)"),
+        Arg::with_name("max-size")
+            .long("max-size")
+            .takes_value(true)
+            .value_name("size")
+            .validator(|size| hshchk::get_size_from_str(&size).map(|_| ()))
+            .help("Only process files of at most specified size (e.g. 100K, 1G)"),
+        Arg::with_name("newer-than")
+            .long("newer-than")
+            .takes_value(true)
+            .value_name("age")
+            .validator(|age| hshchk::get_time_from_age_str(&age).map(|_| ()))
+            .help("Only process files modified within specified age (e.g. 12h, 7d)"),
+        Arg::with_name("older-than")
+            .long("older-than")
+            .takes_value(true)
+            .value_name("age")
+            .validator(|age| hshchk::get_time_from_age_str(&age).map(|_| ()))
+            .help("Only process files modified before specified age (e.g. 12h, 7d)"),
+        Arg::with_name("files-from")
+            .long("files-from")
+            .takes_value(true)
+            .value_name("file")
+            .help(
+                "Only process files listed in specified file (use - for stdin). \
+                 Paths are delimited by NUL characters or new lines, and relative paths \
+                 are relative to the target directory.",
+            ),
+        Arg::with_name("log")
+            .long("log")
+            .takes_value(true)
+            .value_name("file")
+            .help(
+                "Append errors, warnings and the final result with timestamps to specified \
+                 file, even in silent mode",
+            ),
+        Arg::with_name("header").long("header").help(
+            "Write a header with metadata and a checksum of the entries when creating a \
+                 checksum file, so that truncated or edited checksum files are detected",
+        ),
+        Arg::with_name("compress")
+            .long("compress")
+            .takes_value(true)
+            .value_name("method")
+            .possible_values(&hshchk::get_hash_file_compressions())
+            .case_insensitive(true)
+            .help(
+                "Compress the checksum file when creating it (e.g. hshchk.sha1.zst). \
+                 Compressed checksum files are detected when verifying.",
+            ),
+        Arg::with_name("stream").long("stream").help(
+            "Verify files while reading the checksum file instead of loading it in memory \
+             first, for very large checksum files. Extra files are looked for in a \
+             separate pass. Header mismatches are only reported after files are verified.",
+        ),
+        Arg::with_name("no-walk").long("no-walk").help(
+            "Open files listed in the checksum file directly instead of walking the target \
+             directory when verifying. Extra files are not reported.",
+        ),
+        Arg::with_name("per-directory").long("per-directory").help(
+            "Create a checksum file in every directory, covering only its files, and check \
+             the checksum files of all subdirectories when verifying",
+        ),
+        Arg::with_name("sample")
+            .long("sample")
+            .takes_value(true)
+            .value_name("size")
+            .validator(|sample| hshchk::get_sample_size_from_str(&sample).map(|_| ()))
+            .help(
+                "Only verify a random sample of the checksum file entries, given as a number \
+                 of entries (e.g. 500) or a percentage (e.g. 1%). Extra files are not reported. \
+                 Exits with 0 when all sampled files are correct.",
+            ),
+        Arg::with_name("seed")
+            .long("seed")
+            .takes_value(true)
+            .value_name("number")
+            .requires("sample")
+            .validator(|seed| {
+                seed.parse::<u64>()
+                    .map(|_| ())
+                    .map_err(|_| format!("Invalid seed: {}.", seed))
+            })
+            .help("Seed used to pick the sample, to reproduce a previous sample"),
+        key_file_arg(),
+        Arg::with_name("public-key")
+            .long("public-key")
+            .takes_value(true)
+            .value_name("file")
+            .help(
+                "Check the minisign signature of the checksum file (e.g. hshchk.sha1.minisig) \
+                 with specified public key before verifying files",
+            ),
+        sum_arg(),
+    ];
+    args.extend(flag_negation_args());
+    args
+}
+
+fn get_cancellation_token() -> Arc<CancellationToken> {
+    let cancellation_token_source = CancellationTokenSource::new();
+    let cancellation_token = cancellation_token_source.token().clone();
+
+    ctrlc::set_handler(move || {
+        cancellation_token_source.cancel();
+    })
+    .expect("Failed to set Ctrl-C handler.");
+
+    cancellation_token
+}
+
+fn load_config(base_path: Option<&Path>) -> Result<Config, ExitError> {
+    config::load_defaults(base_path).map_err(|error| ExitError::new(EXIT_CONFIG, &error))
+}
+
+// Flags that aren't present are left unset so that defaults from configuration
+// files and environment variables apply.
+fn get_cli_config(matches: &ArgMatches) -> Config {
+    // When a flag and its negation are both given, the last one wins.
+    let flag = |name| {
+        let negation_index = FLAG_NEGATIONS
+            .iter()
+            .find(|(flag_name, _, _)| *flag_name == name)
+            .and_then(|(_, negation, _)| matches.index_of(negation));
+        match (matches.index_of(name), negation_index) {
+            (Some(index), Some(negation_index)) => Some(index > negation_index),
+            (Some(_), None) => Some(true),
+            (None, Some(_)) => Some(false),
+            (None, None) => None,
+        }
+    };
+    let value = |name| matches.value_of(name).map(String::from);
+    let values = |name| {
+        matches
+            .values_of(name)
+            .map(|values| values.map(String::from).collect())
+    };
+    Config {
+        hash_type: value("type"),
+        sum: flag("sum"),
+        size: flag("size"),
+        extra: flag("extra"),
+        silent: flag("silent"),
+        match_pattern: value("match"),
+        ignore_pattern: value("ignore"),
+        match_absolute: flag("match-absolute"),
+        exclude: values("exclude"),
+        include: values("include"),
+        gitignore: flag("gitignore"),
+        min_size: value("min-size"),
+        max_size: value("max-size"),
+        newer_than: value("newer-than"),
+        older_than: value("older-than"),
+        log: value("log"),
+        public_key: value("public-key"),
+        header: flag("header"),
+        compress: value("compress"),
+        stream: flag("stream"),
+        no_walk: flag("no-walk"),
+        per_directory: flag("per-directory"),
+    }
+}
+
+fn run_hash(matches: &ArgMatches) -> Result<(), Box<dyn ::std::error::Error>> {
+    let config = load_config(None)?.merge(get_cli_config(matches));
+    let hash_file_format = hshchk::get_hash_file_format_from_arg(config.sum.unwrap_or_default());
+    let hash_type = config
+        .get_hash_type()
+        .map_err(|error| ExitError::new(EXIT_CONFIG, &error))?
+        .unwrap_or(HashType::SHA1);
+    let digest_length = get_digest_length(matches, hash_type)?;
+    let key = get_key(matches)?;
+    check_key(&key, hash_type)?;
+    let cancellation_token = get_cancellation_token();
+    let mut error_occurred = false;
+    for file_path in matches.values_of("files").unwrap() {
+        let hash_line = if file_path == "-" {
+            hshchk::get_hash_line(
+                hash_type,
+                digest_length,
+                key.as_deref(),
+                hash_file_format,
+                file_path,
+                stdin().lock(),
+                cancellation_token.clone(),
+            )
+        } else {
+            File::open(file_path).and_then(|file| {
+                hshchk::get_hash_line(
+                    hash_type,
+                    digest_length,
+                    key.as_deref(),
+                    hash_file_format,
+                    file_path,
+                    file,
+                    cancellation_token.clone(),
+                )
+            })
+        };
+
+        if cancellation_token.is_canceled() {
+            return Err(Box::new(Error::new(
+                ErrorKind::Interrupted,
+                "The hash process was canceled.",
+            )));
+        }
+
+        match hash_line {
+            Ok(hash_line) => print!("{}", hash_line),
+            Err(error) => {
+                eprintln!("{}: {}", file_path, error);
+                error_occurred = true;
+            }
+        }
+    }
+
+    if error_occurred {
+        return Err(Box::new(Error::other("Some files could not be hashed.")));
+    }
+
+    Ok(())
+}
+
+fn run_sign(matches: &ArgMatches) -> Result<(), Box<dyn ::std::error::Error>> {
+    let directory = matches.value_of("directory").unwrap();
+    let target_path = PathBuf::from(&directory);
+    if !target_path.is_dir() {
+        return Err(Box::new(Error::other(
+            "The specified directory doesn't exist.",
+        )));
+    }
+
+    let config = load_config(Some(&target_path))?.merge(get_cli_config(matches));
+    let hash_type = config
+        .get_hash_type()
+        .map_err(|error| ExitError::new(EXIT_CONFIG, &error))?
+        .unwrap_or(HashType::SHA1);
+    let hash_file_path = hash_file_process::find_hash_file(&target_path, hash_type)
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "Checksum file not found."))?;
+    let secret_key = SecretKey::from_file(Path::new(matches.value_of("key").unwrap()), || {
+        rpassword::read_password_from_tty(Some("Password: "))
+    })?;
+    signature::sign_file(&hash_file_path, &secret_key)?;
+    Ok(())
+}
+
+fn run() -> Result<(), Box<dyn ::std::error::Error>> {
     let app = App::new(crate_name!())
         .setting(AppSettings::ColorAuto)
         .setting(AppSettings::ColoredHelp)
         .setting(AppSettings::DeriveDisplayOrder)
         .setting(AppSettings::UnifiedHelpMessage)
+        .setting(AppSettings::SubcommandsNegateReqs)
         .version(crate_version!())
         .about(crate_description!())
+        .after_help(EXIT_CODES_HELP)
         .arg(Arg::with_name("directory").required(true).help(
             "Target directory. \
              Either create a checksum file or verify files in specified directory. \
              The presence or absence of a checksum file in target directory dictates \
              the operating mode.",
         ))
-        .arg(
-            Arg::with_name("type")
-                .short("t")
-                .long("type")
-                .takes_value(true)
-                .value_name("type")
-                .possible_values(&hshchk::get_hash_types())
-                .case_insensitive(true)
-                .help("Hash function type"),
-        )
+        .args(&process_args())
         .arg(
             Arg::with_name("create")
                 .short("c")
                 .long("create")
                 .help("Force create mode and overwrite checksum file if it exists"),
         )
-        .arg(
-            Arg::with_name("size")
-                .short("f")
-                .long("size")
-                .help("Check file size only"),
+        .subcommand(
+            SubCommand::with_name("create")
+                .about("Create a checksum file, overwriting it if it exists")
+                .arg(directory_arg())
+                .args(&process_args()),
         )
-        .arg(
-            Arg::with_name("extra")
-                .short("r")
-                .long("extra")
-                .help("Report extra files"),
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Verify files against an existing checksum file")
+                .arg(directory_arg())
+                .args(&process_args()),
         )
-        .arg(
-            Arg::with_name("silent")
-                .short("s")
-                .long("silent")
-                .help("Don't output to stdout"),
+        .subcommand(
+            SubCommand::with_name("update")
+                .about(
+                    "Update an existing checksum file with added, removed and resized files \
+                     without rehashing unchanged files",
+                )
+                .arg(directory_arg())
+                .args(&process_args()),
         )
-        .arg(
-            Arg::with_name("match")
-                .short("m")
-                .long("match")
-                .takes_value(true)
-                .value_name("pattern")
-                .help("Process files that matches regex pattern"),
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Report differences between files and an existing checksum file")
+                .arg(directory_arg())
+                .args(&process_args()),
         )
-        .arg(
-            Arg::with_name("ignore")
-                .short("i")
-                .long("ignore")
-                .takes_value(true)
-                .value_name("pattern")
-                .help("Ignore files that matches regex pattern"),
+        .subcommand(
+            SubCommand::with_name("verify-all")
+                .about(
+                    "Verify every checksum file found in a directory tree, including those \
+                     created by other tools (e.g. SHA256SUMS, *.md5, *.sfv), against the files \
+                     of its own directory",
+                )
+                .arg(directory_arg())
+                .args(&process_args()),
         )
-        .arg(
-            Arg::with_name("sum")
-                .short("u")
-                .long("sum")
-                .help("Use hash sum (e.g. sha1sum) file format"),
+        .subcommand(
+            SubCommand::with_name("sign")
+                .about(
+                    "Write a detached minisign signature of the checksum file \
+                     (e.g. hshchk.sha1.minisig)",
+                )
+                .arg(directory_arg())
+                .arg(
+                    Arg::with_name("key")
+                        .short("k")
+                        .long("key")
+                        .takes_value(true)
+                        .value_name("file")
+                        .required(true)
+                        .help("Minisign secret key file (e.g. created with minisign -G)"),
+                )
+                .arg(type_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("hash")
+                .about("Print the checksum of files or of standard input")
+                .arg(
+                    Arg::with_name("files")
+                        .required(true)
+                        .multiple(true)
+                        .help("Files to hash. Use - to read from standard input."),
+                )
+                .arg(type_arg())
+                .arg(length_arg())
+                .arg(key_file_arg())
+                .arg(sum_arg()),
         );
 
     let matches = app.get_matches_safe()?;
+    match matches.subcommand() {
+        ("hash", Some(hash_matches)) => run_hash(hash_matches),
+        ("sign", Some(sign_matches)) => run_sign(sign_matches),
+        ("create", Some(create_matches)) => {
+            run_process(create_matches, Some(HashFileProcessType::Create), false)
+        }
+        ("verify", Some(verify_matches)) => {
+            run_process(verify_matches, Some(HashFileProcessType::Verify), false)
+        }
+        ("update", Some(update_matches)) => {
+            run_process(update_matches, Some(HashFileProcessType::Update), false)
+        }
+        ("diff", Some(diff_matches)) => {
+            run_process(diff_matches, Some(HashFileProcessType::Diff), false)
+        }
+        ("verify-all", Some(verify_all_matches)) => {
+            run_process(verify_all_matches, Some(HashFileProcessType::Verify), true)
+        }
+        _ => run_process(&matches, None, false),
+    }
+}
 
+fn run_process(
+    matches: &ArgMatches,
+    process_type: Option<HashFileProcessType>,
+    verify_all: bool,
+) -> Result<(), Box<dyn ::std::error::Error>> {
     let directory = matches.value_of("directory").unwrap();
     let target_path = PathBuf::from(&directory);
     if !target_path.is_dir() {
-        return Err(Box::new(Error::new(
-            ErrorKind::Other,
+        return Err(Box::new(Error::other(
             "The specified directory doesn't exist.",
         )));
     }
 
-    let hash_file_format = hshchk::get_hash_file_format_from_arg(matches.is_present("sum"));
-    let hash_type =
-        hshchk::get_hash_type_from_str(&matches.value_of("type").unwrap_or("SHA1").to_uppercase());
+    let file_list = match matches.value_of("files-from") {
+        Some("-") => Some(hshchk::get_file_list(stdin().lock())?),
+        Some(file_list_path) => Some(hshchk::get_file_list(File::open(file_list_path)?)?),
+        None => None,
+    };
 
-    let cancellation_token_source = CancellationTokenSource::new();
-    let main_cancellation_token = cancellation_token_source.token();
-    let cancellation_token = main_cancellation_token.clone();
-
-    ctrlc::set_handler(move || {
-        cancellation_token_source.cancel();
-    })
-    .expect("Failed to set Ctrl-C handler.");
+    let config = load_config(Some(&target_path))?.merge(get_cli_config(matches));
+    let options = config
+        .to_options(&target_path)
+        .map_err(|error| ExitError::new(EXIT_CONFIG, &error))?;
+    let key = get_key(matches)?;
+    let public_key = match &config.public_key {
+        Some(public_key_path) => Some(PublicKey::from_file(Path::new(public_key_path))?),
+        None => None,
+    };
 
+    let cancellation_token = get_cancellation_token();
     let processor = HashFileProcessor::new(HashFileProcessOptions {
-        base_path: target_path,
-        hash_file_format: Some(hash_file_format),
-        hash_type: Some(hash_type),
+        digest_length: matches
+            .value_of("length")
+            .map(|length| length.parse().unwrap()),
+        key,
+        public_key,
+        process_type,
+        verify_all: Some(verify_all),
+        sample: matches
+            .value_of("sample")
+            .map(|sample| hshchk::get_sample_size_from_str(sample).unwrap()),
+        seed: matches.value_of("seed").map(|seed| seed.parse().unwrap()),
         force_create: Some(matches.is_present("create")),
-        report_extra: Some(matches.is_present("extra")),
-        size_only: Some(matches.is_present("size")),
-        match_pattern: matches.value_of("match"),
-        ignore_pattern: matches.value_of("ignore"),
+        file_list,
+        ..options
     });
+    // The hash type of an existing checksum file is only known once it is found.
+    get_digest_length(matches, processor.get_hash_type())?;
 
     let process_type = processor.get_process_type();
-    let ui = ui::UI::new(processor, matches.is_present("silent"));
+    let mut ui = ui::UI::new(processor, config.silent.unwrap_or_default());
+    if let Some(log_path) = &config.log {
+        ui.set_report_log(ReportLog::open(Path::new(log_path))?);
+    }
 
-    match ui.run(cancellation_token, process_type) {
-        HashFileProcessResult::Error => Err(Box::new(Error::new(
-            ErrorKind::Other,
-            "The hash check process failed.",
-        ))),
-        HashFileProcessResult::Canceled => Err(Box::new(Error::new(
-            ErrorKind::Interrupted,
+    let (result, stats) = ui.run(cancellation_token, process_type);
+    match result {
+        HashFileProcessResult::Error => {
+            // Corruption takes precedence over I/O errors, which take precedence over
+            // missing files.
+            let exit_code = if stats.mismatches > 0 {
+                EXIT_MISMATCH
+            } else if stats.errors > 0 {
+                EXIT_IO_ERROR
+            } else if stats.missing > 0 {
+                EXIT_MISSING
+            } else {
+                EXIT_FAILURE
+            };
+            Err(Box::new(ExitError::new(
+                exit_code,
+                "The hash check process failed.",
+            )))
+        }
+        HashFileProcessResult::Canceled => Err(Box::new(ExitError::new(
+            EXIT_CANCELED,
             "The hash check process was canceled.",
         ))),
-        HashFileProcessResult::NoFilesProcessed => Err(Box::new(Error::new(
-            ErrorKind::NotFound,
+        HashFileProcessResult::NoFilesProcessed => Err(Box::new(ExitError::new(
+            EXIT_NO_FILES_PROCESSED,
             "No files were processed.",
         ))),
-        HashFileProcessResult::Success => Ok(()),
+        HashFileProcessResult::Success | HashFileProcessResult::Sampled => Ok(()),
     }
 }
 
@@ -140,18 +765,10 @@ fn main() {
     if let Err(error) = run() {
         if let Some(clap_error) = error.downcast_ref::<clap::Error>() {
             eprint!("{}", clap_error); // `clap` errors already have newlines
-
-            match clap_error.kind {
-                clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => {
-                    // The exit code should not indicate an error for --help / --version
-                    std::process::exit(0)
-                }
-                _ => (),
-            }
         } else {
             eprintln!("{}", error);
         }
 
-        std::process::exit(1);
+        std::process::exit(get_exit_code(error.as_ref()));
     }
 }
@@ -0,0 +1,128 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub static HASHCHK_IGNORE_FILE_NAME: &str = ".hshchkignore";
+pub static GIT_IGNORE_FILE_NAME: &str = ".gitignore";
+
+// Ignore files are loaded lazily, once per directory, and matched using
+// gitignore semantics. Rules found in deeper directories take precedence
+// over rules found in their parents.
+pub struct IgnoreFilter {
+    base_path: PathBuf,
+    ignore_file_names: Vec<&'static str>,
+    exclude: Option<Gitignore>,
+    include: Option<Gitignore>,
+    directory_matchers: HashMap<PathBuf, Option<Gitignore>>,
+}
+
+impl IgnoreFilter {
+    pub fn new(
+        base_path: &Path,
+        use_gitignore: bool,
+        exclude_patterns: &[&str],
+        include_patterns: &[&str],
+    ) -> Self {
+        let mut ignore_file_names = vec![HASHCHK_IGNORE_FILE_NAME];
+        if use_gitignore {
+            ignore_file_names.push(GIT_IGNORE_FILE_NAME);
+        }
+
+        IgnoreFilter {
+            base_path: base_path.to_path_buf(),
+            ignore_file_names,
+            exclude: build_matcher(base_path, exclude_patterns),
+            include: build_matcher(base_path, include_patterns),
+            directory_matchers: HashMap::new(),
+        }
+    }
+    pub fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        if !path.starts_with(&self.base_path) || path == self.base_path {
+            return false;
+        }
+
+        if let Some(include) = &self.include {
+            if !is_dir
+                && !include
+                    .matched_path_or_any_parents(path, is_dir)
+                    .is_ignore()
+            {
+                return true;
+            }
+        }
+
+        if let Some(exclude) = &self.exclude {
+            match exclude.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => (),
+            }
+        }
+
+        let mut directory = path.parent();
+        while let Some(directory_path) = directory {
+            if let Some(matcher) = self.get_directory_matcher(directory_path) {
+                match matcher.matched_path_or_any_parents(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => (),
+                }
+            }
+
+            if directory_path == self.base_path {
+                break;
+            }
+
+            directory = directory_path.parent();
+        }
+
+        false
+    }
+    fn get_directory_matcher(&mut self, directory_path: &Path) -> Option<&Gitignore> {
+        let ignore_file_names = &self.ignore_file_names;
+        self.directory_matchers
+            .entry(directory_path.to_path_buf())
+            .or_insert_with(|| {
+                let mut builder = GitignoreBuilder::new(directory_path);
+                let mut found = false;
+                for ignore_file_name in ignore_file_names {
+                    let ignore_file_path = directory_path.join(ignore_file_name);
+                    if ignore_file_path.is_file() {
+                        // Invalid lines are skipped, like git does.
+                        let _ = builder.add(ignore_file_path);
+                        found = true;
+                    }
+                }
+
+                if found {
+                    builder.build().ok()
+                } else {
+                    None
+                }
+            })
+            .as_ref()
+    }
+}
+
+fn build_matcher(base_path: &Path, patterns: &[&str]) -> Option<Gitignore> {
+    if patterns.is_empty() {
+        return None;
+    }
+
+    let mut builder = GitignoreBuilder::new(base_path);
+    for pattern in patterns {
+        // Patterns are checked by `Config::to_options`. Invalid ones are skipped, like
+        // invalid lines of ignore files.
+        let _ = builder.add_line(None, pattern);
+    }
+
+    builder.build().ok()
+}
+
+pub fn check_glob_pattern(pattern: &str) -> Result<(), String> {
+    GitignoreBuilder::new("")
+        .add_line(None, pattern)
+        .map(|_| ())
+        .map_err(|why| format!("Invalid glob pattern {}: {}", pattern, why))
+}
@@ -1,32 +1,76 @@
 use crate::block_hasher::BlockHasher;
+use crate::crc32::Crc32;
 use crate::file_hash::FileHash;
+use crate::hash_file::HashFileEntry;
+use crate::reader_hash::ReaderHash;
+use crate::variable_digest::VariableDigest;
+use crate::xxh3::{Xxh3_128, Xxh3_64};
 use blake2::{Blake2b, Blake2s};
+use cancellation::CancellationToken;
+use crossbeam::channel::Sender;
 use md5::Md5;
 use sha1::Sha1;
-use sha2::{Sha256, Sha512};
+use sha2::{Sha224, Sha256, Sha384, Sha512};
+use sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512};
 use std::fs::File;
-use std::path::{Path, MAIN_SEPARATOR};
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use strum::IntoEnumIterator;
 use strum_macros::{EnumIter, EnumString, IntoStaticStr};
 mod block_hasher;
+pub mod config;
+mod crc32;
 mod file_hash;
 mod file_tree;
 mod hash_file;
 pub mod hash_file_process;
+mod ignore_filter;
 mod output;
+mod path_set;
+mod reader_hash;
+pub mod report_log;
+mod sample;
+pub mod signature;
 mod speed;
 mod tty;
 pub mod ui;
+mod variable_digest;
+mod xxh3;
 
+pub use block_hasher::HashProgress;
+
+#[allow(non_camel_case_types)]
 #[derive(Clone, Copy, Debug, EnumIter, EnumString, IntoStaticStr, PartialEq)]
 pub enum HashType {
     MD5,
     SHA1,
+    SHA224,
     SHA256,
+    SHA384,
     SHA512,
+    #[strum(serialize = "SHA3-224")]
+    SHA3_224,
+    #[strum(serialize = "SHA3-256")]
+    SHA3_256,
+    #[strum(serialize = "SHA3-384")]
+    SHA3_384,
+    #[strum(serialize = "SHA3-512")]
+    SHA3_512,
     BLAKE2B,
     BLAKE2S,
     BLAKE3,
+    #[strum(serialize = "XXH3")]
+    XXH3_64,
+    XXH128,
+    CRC32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Digest {
+    pub hex: String,
+    pub bytes_processed: u64,
 }
 
 #[derive(Clone, Copy, Debug, EnumIter, EnumString, IntoStaticStr, PartialEq)]
@@ -35,6 +79,27 @@ pub enum HashFileFormat {
     HashSum,   // hash<space><space/asterisk>filepath
 }
 
+#[derive(Clone, Copy, Debug, EnumIter, EnumString, IntoStaticStr, PartialEq)]
+pub enum HashFileCompression {
+    #[strum(serialize = "none")]
+    None,
+    #[strum(serialize = "gzip")]
+    Gzip, // .gz
+    #[strum(serialize = "zstd")]
+    Zstd, // .zst
+}
+
+// Number of checksum file entries verified when sampling.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleSize {
+    Count(u64),
+    Percent(f64),
+}
+
+// BLAKE3 digests can be of any length. This keeps them within the digest
+// size accepted when loading checksum files.
+const MAX_XOF_DIGEST_LENGTH: usize = 4_096;
+
 pub fn replaceable_separator() -> &'static str {
     match MAIN_SEPARATOR {
         '/' => "\\",
@@ -42,8 +107,12 @@ pub fn replaceable_separator() -> &'static str {
     }
 }
 
+// CRC32 is only used to verify SFV files, it can't be selected.
 pub fn get_hash_types() -> Vec<&'static str> {
-    HashType::iter().map(|ht| ht.into()).collect()
+    HashType::iter()
+        .filter(|hash_type| *hash_type != HashType::CRC32)
+        .map(|ht| ht.into())
+        .collect()
 }
 
 pub fn get_hash_type_from_str(type_str: &str) -> HashType {
@@ -58,57 +127,338 @@ pub fn get_hash_file_format_from_arg(sum_format_present: bool) -> HashFileFormat
     }
 }
 
-fn open_file(file_path: &Path) -> File {
-    match File::open(file_path) {
-        Err(why) => panic!("Couldn't open {}: {}.", file_path.display(), why),
-        Ok(file) => file,
+pub fn get_hash_file_compressions() -> Vec<&'static str> {
+    HashFileCompression::iter().map(|hfc| hfc.into()).collect()
+}
+
+// Compressed checksum files are named after their uncompressed counterpart
+// with an additional extension (e.g. `hshchk.sha256.zst`).
+pub fn get_hash_file_compression_extension(
+    hash_file_compression: HashFileCompression,
+) -> Option<&'static str> {
+    match hash_file_compression {
+        HashFileCompression::None => None,
+        HashFileCompression::Gzip => Some("gz"),
+        HashFileCompression::Zstd => Some("zst"),
     }
 }
 
-fn create_file(file_path: &Path) -> File {
-    match File::create(file_path) {
-        Err(why) => panic!("Couldn't create {}: {}.", file_path.display(), why),
-        Ok(file) => file,
+pub fn get_hash_file_compression(file_path: &Path) -> HashFileCompression {
+    let extension = file_path
+        .extension()
+        .and_then(|extension| extension.to_str());
+    HashFileCompression::iter()
+        .find(|hfc| get_hash_file_compression_extension(*hfc) == extension)
+        .unwrap_or(HashFileCompression::None)
+}
+
+// Digest lengths are expressed in bits, like `b2sum -l` does.
+pub fn get_default_digest_length(hash_type: HashType) -> usize {
+    match hash_type {
+        HashType::MD5 => 128,
+        HashType::SHA1 => 160,
+        HashType::SHA224 | HashType::SHA3_224 => 224,
+        HashType::SHA256 | HashType::SHA3_256 => 256,
+        HashType::SHA384 | HashType::SHA3_384 => 384,
+        HashType::SHA512 | HashType::SHA3_512 => 512,
+        HashType::BLAKE2B => 512,
+        HashType::BLAKE2S => 256,
+        HashType::BLAKE3 => 256,
+        HashType::XXH3_64 => 64,
+        HashType::XXH128 => 128,
+        HashType::CRC32 => 32,
     }
 }
 
-fn get_md5_file_hasher(file_path: &Path) -> FileHash<Md5> {
-    FileHash::new(file_path)
+pub fn supports_digest_length(hash_type: HashType) -> bool {
+    matches!(
+        hash_type,
+        HashType::BLAKE2B | HashType::BLAKE2S | HashType::BLAKE3
+    )
 }
 
-fn get_sha1_file_hasher(file_path: &Path) -> FileHash<Sha1> {
-    FileHash::new(file_path)
+pub fn is_valid_digest_length(hash_type: HashType, digest_length: usize) -> bool {
+    let max_digest_length = match hash_type {
+        HashType::BLAKE3 => MAX_XOF_DIGEST_LENGTH,
+        _ if supports_digest_length(hash_type) => get_default_digest_length(hash_type),
+        _ => return digest_length == get_default_digest_length(hash_type),
+    };
+
+    digest_length > 0 && digest_length.is_multiple_of(8) && digest_length <= max_digest_length
 }
 
-fn get_sha256_file_hasher(file_path: &Path) -> FileHash<Sha256> {
-    FileHash::new(file_path)
+// BLAKE2 and BLAKE3 have a keyed mode. SHA256 is keyed using HMAC.
+pub fn supports_key(hash_type: HashType) -> bool {
+    matches!(
+        hash_type,
+        HashType::BLAKE2B | HashType::BLAKE2S | HashType::BLAKE3 | HashType::SHA256
+    )
 }
 
-fn get_sha512_file_hasher(file_path: &Path) -> FileHash<Sha512> {
-    FileHash::new(file_path)
+pub fn get_keyed_hash_name(hash_type: HashType) -> &'static str {
+    match hash_type {
+        HashType::SHA256 => "HMAC-SHA256",
+        _ => hash_type.into(),
+    }
+}
+
+// Parses sizes such as `1024`, `500K`, `20M`, `1G` or `2T` (binary multiples).
+pub fn get_size_from_str(size_str: &str) -> std::result::Result<u64, String> {
+    let size_str = size_str.trim();
+    let (number, multiplier) = match size_str.char_indices().last() {
+        Some((index, unit)) if unit.is_ascii_alphabetic() => {
+            let multiplier = match unit.to_ascii_uppercase() {
+                'B' => 1u64,
+                'K' => 1 << 10,
+                'M' => 1 << 20,
+                'G' => 1 << 30,
+                'T' => 1 << 40,
+                _ => return Err(format!("Invalid size unit: {}.", unit)),
+            };
+            (&size_str[..index], multiplier)
+        }
+        _ => (size_str, 1),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid size: {}.", size_str))
 }
 
-fn get_blake2b_file_hasher(file_path: &Path) -> FileHash<Blake2b> {
-    FileHash::new(file_path)
+// Parses sample sizes such as `500` (entries) or `1%` (of the entries).
+pub fn get_sample_size_from_str(sample_str: &str) -> std::result::Result<SampleSize, String> {
+    let sample_str = sample_str.trim();
+    let sample_size = match sample_str.strip_suffix('%') {
+        Some(percent) => percent
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|percent| *percent > 0.0 && *percent <= 100.0)
+            .map(SampleSize::Percent),
+        None => sample_str
+            .parse::<u64>()
+            .ok()
+            .filter(|count| *count > 0)
+            .map(SampleSize::Count),
+    };
+
+    sample_size.ok_or_else(|| format!("Invalid sample size: {}.", sample_str))
 }
 
-fn get_blake2s_file_hasher(file_path: &Path) -> FileHash<Blake2s> {
-    FileHash::new(file_path)
+// Parses ages such as `30s`, `15m`, `12h`, `7d` or `2w` and returns the
+// corresponding point in time relative to now.
+pub fn get_time_from_age_str(age_str: &str) -> std::result::Result<SystemTime, String> {
+    let age_str = age_str.trim();
+    let (number, seconds) = match age_str.char_indices().last() {
+        Some((index, unit)) if unit.is_ascii_alphabetic() => {
+            let seconds = match unit.to_ascii_lowercase() {
+                's' => 1u64,
+                'm' => 60,
+                'h' => 3_600,
+                'd' => 86_400,
+                'w' => 604_800,
+                _ => return Err(format!("Invalid age unit: {}.", unit)),
+            };
+            (&age_str[..index], seconds)
+        }
+        _ => return Err(format!("Missing age unit (s, m, h, d or w): {}.", age_str)),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(seconds))
+        .and_then(|age| SystemTime::now().checked_sub(Duration::from_secs(age)))
+        .ok_or_else(|| format!("Invalid age: {}.", age_str))
+}
+
+// Reads a list of file paths delimited by NUL characters (e.g. `find -print0`)
+// or, when no NUL character is present, by new lines.
+// Paths aren't required to be valid Unicode: files with ill-formed Unicode names
+// are reported when processed.
+pub fn get_file_list<R: Read>(mut reader: R) -> Result<Vec<PathBuf>> {
+    let mut content = Vec::new();
+    reader.read_to_end(&mut content)?;
+    let delimiter = if content.contains(&b'\0') {
+        b'\0'
+    } else {
+        b'\n'
+    };
+    Ok(content
+        .split(|byte| *byte == delimiter)
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .map(get_path_from_bytes)
+        .collect())
 }
 
-fn get_blake3_file_hasher(file_path: &Path) -> FileHash<blake3::Hasher> {
-    FileHash::new(file_path)
+#[cfg(unix)]
+fn get_path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
 }
 
-fn get_file_hasher<'a>(hash_type: HashType, file_path: &'a Path) -> Box<dyn BlockHasher + 'a> {
+// Ill-formed Unicode can't be represented in Windows paths read as bytes.
+#[cfg(not(unix))]
+fn get_path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+// Hashes data from `reader` and formats the result as a checksum file line.
+pub fn get_hash_line<R: Read>(
+    hash_type: HashType,
+    digest_length: Option<usize>,
+    key: Option<&[u8]>,
+    hash_file_format: HashFileFormat,
+    file_path: &str,
+    reader: R,
+    cancellation_token: Arc<CancellationToken>,
+) -> Result<String> {
+    let digest = hash_reader_keyed(
+        hash_type,
+        digest_length,
+        key,
+        reader,
+        cancellation_token,
+        None,
+    )?;
+    Ok(hash_file::format_entry(
+        &HashFileEntry {
+            file_path: file_path.to_string(),
+            size: Some(digest.bytes_processed),
+            digest: digest.hex,
+        },
+        hash_type,
+        hash_file_format,
+    ))
+}
+
+// Hashes all data from `reader`. Progress is reported through `progress_sender`
+// and an `Interrupted` error is returned if the operation is canceled.
+pub fn hash_reader<R: Read>(
+    hash_type: HashType,
+    reader: R,
+    cancellation_token: Arc<CancellationToken>,
+    progress_sender: Option<Sender<HashProgress>>,
+) -> Result<Digest> {
+    hash_reader_with_digest_length(hash_type, None, reader, cancellation_token, progress_sender)
+}
+
+// Same as `hash_reader`, with a digest length in bits for hash types that
+// support it (see `supports_digest_length`). `None` uses the default length.
+pub fn hash_reader_with_digest_length<R: Read>(
+    hash_type: HashType,
+    digest_length: Option<usize>,
+    reader: R,
+    cancellation_token: Arc<CancellationToken>,
+    progress_sender: Option<Sender<HashProgress>>,
+) -> Result<Digest> {
+    hash_reader_keyed(
+        hash_type,
+        digest_length,
+        None,
+        reader,
+        cancellation_token,
+        progress_sender,
+    )
+}
+
+// Same as `hash_reader_with_digest_length`, using the keyed mode of the hash
+// function when a key is given (see `supports_key`).
+pub fn hash_reader_keyed<R: Read>(
+    hash_type: HashType,
+    digest_length: Option<usize>,
+    key: Option<&[u8]>,
+    reader: R,
+    cancellation_token: Arc<CancellationToken>,
+    progress_sender: Option<Sender<HashProgress>>,
+) -> Result<Digest> {
+    let hasher = get_keyed_reader_hasher(hash_type, digest_length, key, reader)?;
+    compute_digest(hasher, cancellation_token, progress_sender)
+}
+
+pub fn hash_file(
+    hash_type: HashType,
+    file_path: &Path,
+    cancellation_token: Arc<CancellationToken>,
+    progress_sender: Option<Sender<HashProgress>>,
+) -> Result<Digest> {
+    FileHash::open(hash_type, None, None, file_path)?.compute(cancellation_token, progress_sender)
+}
+
+fn get_keyed_reader_hasher<'a, R: Read + 'a>(
+    hash_type: HashType,
+    digest_length: Option<usize>,
+    key: Option<&[u8]>,
+    reader: R,
+) -> Result<Box<dyn BlockHasher + 'a>> {
+    let default_digest_length = get_default_digest_length(hash_type);
+    let digest_length = digest_length.unwrap_or(default_digest_length);
+    if key.is_some() || digest_length != default_digest_length {
+        let variable_digest = VariableDigest::new(hash_type, digest_length, key)
+            .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+        Ok(Box::new(ReaderHash::new_with_hasher(
+            reader,
+            variable_digest,
+        )))
+    } else {
+        Ok(get_reader_hasher(hash_type, reader))
+    }
+}
+
+fn compute_digest(
+    mut hasher: Box<dyn BlockHasher + '_>,
+    cancellation_token: Arc<CancellationToken>,
+    progress_sender: Option<Sender<HashProgress>>,
+) -> Result<Digest> {
+    if let Some(sender) = progress_sender {
+        hasher.set_bytes_processed_event_sender(sender);
+    }
+
+    let bytes_processed = hasher.compute(cancellation_token.clone())?;
+    if cancellation_token.is_canceled() {
+        return Err(Error::new(
+            ErrorKind::Interrupted,
+            "The hash process was canceled.",
+        ));
+    }
+
+    Ok(Digest {
+        hex: hasher.digest(),
+        bytes_processed,
+    })
+}
+
+fn create_file(file_path: &Path) -> File {
+    match File::create(file_path) {
+        Err(why) => panic!("Couldn't create {}: {}.", file_path.display(), why),
+        Ok(file) => file,
+    }
+}
+
+fn get_reader_hasher<'a, R: Read + 'a>(
+    hash_type: HashType,
+    reader: R,
+) -> Box<dyn BlockHasher + 'a> {
     match hash_type {
-        HashType::MD5 => Box::new(get_md5_file_hasher(file_path)),
-        HashType::SHA1 => Box::new(get_sha1_file_hasher(file_path)),
-        HashType::SHA256 => Box::new(get_sha256_file_hasher(file_path)),
-        HashType::SHA512 => Box::new(get_sha512_file_hasher(file_path)),
-        HashType::BLAKE2B => Box::new(get_blake2b_file_hasher(file_path)),
-        HashType::BLAKE2S => Box::new(get_blake2s_file_hasher(file_path)),
-        HashType::BLAKE3 => Box::new(get_blake3_file_hasher(file_path)),
+        HashType::MD5 => Box::new(ReaderHash::<Md5, R>::new(reader)),
+        HashType::SHA1 => Box::new(ReaderHash::<Sha1, R>::new(reader)),
+        HashType::SHA224 => Box::new(ReaderHash::<Sha224, R>::new(reader)),
+        HashType::SHA256 => Box::new(ReaderHash::<Sha256, R>::new(reader)),
+        HashType::SHA384 => Box::new(ReaderHash::<Sha384, R>::new(reader)),
+        HashType::SHA512 => Box::new(ReaderHash::<Sha512, R>::new(reader)),
+        HashType::SHA3_224 => Box::new(ReaderHash::<Sha3_224, R>::new(reader)),
+        HashType::SHA3_256 => Box::new(ReaderHash::<Sha3_256, R>::new(reader)),
+        HashType::SHA3_384 => Box::new(ReaderHash::<Sha3_384, R>::new(reader)),
+        HashType::SHA3_512 => Box::new(ReaderHash::<Sha3_512, R>::new(reader)),
+        HashType::BLAKE2B => Box::new(ReaderHash::<Blake2b, R>::new(reader)),
+        HashType::BLAKE2S => Box::new(ReaderHash::<Blake2s, R>::new(reader)),
+        HashType::BLAKE3 => Box::new(ReaderHash::<blake3::Hasher, R>::new(reader)),
+        HashType::XXH3_64 => Box::new(ReaderHash::<Xxh3_64, R>::new(reader)),
+        HashType::XXH128 => Box::new(ReaderHash::<Xxh3_128, R>::new(reader)),
+        HashType::CRC32 => Box::new(ReaderHash::<Crc32, R>::new(reader)),
     }
 }
 
@@ -118,22 +468,24 @@ extern crate test_shared;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::hash_file::HashFile;
+    use crate::hash_file::{HashFile, HashFileReader};
+    use crate::path_set::PathSet;
+    use crate::sample;
+    use crate::signature::{PublicKey, SecretKey};
     use cancellation::CancellationTokenSource;
     use crossbeam::channel::unbounded;
-    use hash_file::HashFileEntry;
     use std::fs;
 
     // block hasher
 
     // ...
 
-    // file hash
+    // reader hash
     #[test]
     fn file_hash_bytes_processed_event_sender_undefined() {
         let file = test_shared::create_tmp_file("");
-        let file_hash: FileHash<Md5> = FileHash::new(&file);
-        assert_eq!(file_hash.is_bytes_processed_event_sender_defined(), false);
+        let file_hash: ReaderHash<Md5, File> = ReaderHash::new(File::open(&file).unwrap());
+        assert!(!file_hash.is_bytes_processed_event_sender_defined());
         drop(file_hash); // force release of file handle (Windows)
         fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
@@ -141,10 +493,10 @@ mod tests {
     #[test]
     fn file_hash_bytes_processed_event_sender_defined() {
         let file = test_shared::create_tmp_file("");
-        let mut file_hash: FileHash<Md5> = FileHash::new(&file);
+        let mut file_hash: ReaderHash<Md5, File> = ReaderHash::new(File::open(&file).unwrap());
         let (sender, _) = unbounded();
         file_hash.set_bytes_processed_event_sender(sender);
-        assert_eq!(file_hash.is_bytes_processed_event_sender_defined(), true);
+        assert!(file_hash.is_bytes_processed_event_sender_defined());
         drop(file_hash); // force release of file handle (Windows)
         fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
@@ -152,40 +504,35 @@ mod tests {
     #[test]
     fn file_hash_empty_file() {
         let file = test_shared::create_tmp_file("");
-        let mut file_hash = get_md5_file_hasher(&file);
         let cancellation_token_source = CancellationTokenSource::new();
         let cancellation_token = cancellation_token_source.token();
-        file_hash.compute(cancellation_token.clone());
-        let digest = file_hash.digest();
-        assert_eq!(digest, "d41d8cd98f00b204e9800998ecf8427e");
-        drop(file_hash); // force release of file handle (Windows)
+        let digest = hash_file(HashType::MD5, &file, cancellation_token.clone(), None).unwrap();
+        assert_eq!(digest.hex, "d41d8cd98f00b204e9800998ecf8427e");
         fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
 
     #[test]
     fn file_hash_data_file() {
         let file = test_shared::create_tmp_file("data");
-        let mut file_hash = get_md5_file_hasher(&file);
         let cancellation_token_source = CancellationTokenSource::new();
         let cancellation_token = cancellation_token_source.token();
-        file_hash.compute(cancellation_token.clone());
-        let digest = file_hash.digest();
-        assert_eq!(digest, "8d777f385d3dfec8815d20f7496026dc");
-        drop(file_hash); // force release of file handle (Windows)
+        let digest = hash_file(HashType::MD5, &file, cancellation_token.clone(), None).unwrap();
+        assert_eq!(digest.hex, "8d777f385d3dfec8815d20f7496026dc");
         fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
 
     #[test]
     fn file_hash_data_two_blocks() {
         let file = test_shared::create_tmp_file("datadata");
-        let mut file_hash: FileHash<Md5> = FileHash::new_with_buffer_size(&file, 2);
+        let mut file_hash: ReaderHash<Md5, File> =
+            ReaderHash::new_with_buffer_size(File::open(&file).unwrap(), 2);
         let (sender, receiver) = unbounded();
         file_hash.set_bytes_processed_event_sender_with_bytes_processed_notification_block_size(
             sender, 4,
         );
         let cancellation_token_source = CancellationTokenSource::new();
         let cancellation_token = cancellation_token_source.token();
-        file_hash.compute(cancellation_token.clone());
+        file_hash.compute(cancellation_token.clone()).unwrap();
         let digest = file_hash.digest();
         assert_eq!(digest, "511ae0b1c13f95e5f08f1a0dd3da3d93");
         assert_eq!(4, receiver.recv().unwrap().bytes_processed);
@@ -195,87 +542,814 @@ mod tests {
         fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
 
+    #[test]
+    fn file_hash_open_error() {
+        let dir = test_shared::create_tmp_dir();
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        let file_path = dir.join("missing");
+        assert!(hash_file(HashType::MD5, &file_path, cancellation_token.clone(), None).is_err());
+        fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_hash_read_error() {
+        let dir = test_shared::create_tmp_dir();
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        assert!(hash_file(HashType::MD5, &dir, cancellation_token.clone(), None).is_err());
+        fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    }
+
+    #[test]
+    fn hash_reader_data() {
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        let (sender, receiver) = unbounded();
+        let digest = hash_reader(
+            HashType::SHA1,
+            "data".as_bytes(),
+            cancellation_token.clone(),
+            Some(sender),
+        )
+        .unwrap();
+        assert_eq!(
+            Digest {
+                hex: "a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd".into(),
+                bytes_processed: 4
+            },
+            digest
+        );
+        assert!(receiver.try_recv().is_err()); // below notification block size
+    }
+
+    #[test]
+    fn hash_reader_canceled() {
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        cancellation_token_source.cancel();
+        let error = hash_reader(
+            HashType::SHA1,
+            "data".as_bytes(),
+            cancellation_token.clone(),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(ErrorKind::Interrupted, error.kind());
+    }
+
+    #[test]
+    fn get_hash_line_formats() {
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        let line = get_hash_line(
+            HashType::MD5,
+            None,
+            None,
+            HashFileFormat::HashCheck,
+            "-",
+            "data".as_bytes(),
+            cancellation_token.clone(),
+        )
+        .unwrap();
+        assert_eq!("-|4|8d777f385d3dfec8815d20f7496026dc\n", line);
+        let line = get_hash_line(
+            HashType::MD5,
+            None,
+            None,
+            HashFileFormat::HashSum,
+            "file",
+            "data".as_bytes(),
+            cancellation_token.clone(),
+        )
+        .unwrap();
+        assert_eq!("8d777f385d3dfec8815d20f7496026dc *file\n", line);
+    }
+
+    #[test]
+    fn hash_reader_sha2_sha3() {
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        let digests = vec![
+            (
+                HashType::SHA224,
+                "f4739673acc03c424343b452787ee23dd62999a8a9f14f4250995769",
+            ),
+            (
+                HashType::SHA384,
+                "2039e0f0b92728499fb88e23ebc3cfd0554b28400b0ed7b753055c88b5865c3c\
+                 2aa72c6a1a9ae0a755d87900a4a6ff41",
+            ),
+            (
+                HashType::SHA3_224,
+                "4516c726a95c8d5dadcf1d252fac1f2c2e0c435a2cd76db0be854bf7",
+            ),
+            (
+                HashType::SHA3_256,
+                "efda893aa850b0c0e61f33325615b9d93bcf6b42d60d8f5d37ebc720fd4e3daf",
+            ),
+            (
+                HashType::SHA3_384,
+                "92e8f413ff748bf40756b0b84543784b9e40611b67d3c6cc4cb685e749b834ec\
+                 902966eb8043ec222dbdab56688b86c4",
+            ),
+            (
+                HashType::SHA3_512,
+                "ceca4daf960c2bbfb4a9edaca9b8137a801b65bae377e0f534ef9141c8684c0f\
+                 edc1768d1afde9766572846c42b935f61177eaf97d355fa8dc2bca3fecfa754d",
+            ),
+        ];
+        for (hash_type, hex) in digests {
+            let digest = hash_reader(
+                hash_type,
+                "data".as_bytes(),
+                cancellation_token.clone(),
+                None,
+            )
+            .unwrap();
+            assert_eq!(hex, digest.hex);
+        }
+    }
+
+    #[test]
+    fn hash_reader_xxh3() {
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        let digest = hash_reader(
+            HashType::XXH3_64,
+            "".as_bytes(),
+            cancellation_token.clone(),
+            None,
+        )
+        .unwrap();
+        assert_eq!("2d06800538d394c2", digest.hex);
+        let digest = hash_reader(
+            HashType::XXH128,
+            "".as_bytes(),
+            cancellation_token.clone(),
+            None,
+        )
+        .unwrap();
+        assert_eq!("99aa06d3014798d86001c324468d497f", digest.hex);
+    }
+
+    #[test]
+    fn hash_reader_crc32() {
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        let digest = hash_reader(
+            HashType::CRC32,
+            "123456789".as_bytes(),
+            cancellation_token.clone(),
+            None,
+        )
+        .unwrap();
+        assert_eq!("cbf43926", digest.hex);
+    }
+
+    #[test]
+    fn hash_reader_variable_digest_length() {
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        let hash = |hash_type, digest_length| {
+            hash_reader_with_digest_length(
+                hash_type,
+                digest_length,
+                "data".as_bytes(),
+                cancellation_token.clone(),
+                None,
+            )
+        };
+        assert_eq!(
+            "a035872d6af8639ede962dfe7536b0c150b590f3234a922fb7064cd11971b58e",
+            hash(HashType::BLAKE2B, Some(256)).unwrap().hex
+        );
+        assert_eq!("aa", hash(HashType::BLAKE2B, Some(8)).unwrap().hex);
+        assert_eq!(
+            "63de4a283fb889f481c68c3a96730c1b",
+            hash(HashType::BLAKE2S, Some(128)).unwrap().hex
+        );
+        // BLAKE3 digests of any length share the same prefix.
+        let blake3_default = hash(HashType::BLAKE3, None).unwrap().hex;
+        assert_eq!(
+            blake3_default,
+            hash(HashType::BLAKE3, Some(256)).unwrap().hex
+        );
+        assert_eq!(
+            blake3_default[..32],
+            hash(HashType::BLAKE3, Some(128)).unwrap().hex
+        );
+        let blake3_extended = hash(HashType::BLAKE3, Some(1024)).unwrap().hex;
+        assert_eq!(256, blake3_extended.len());
+        assert!(blake3_extended.starts_with(&blake3_default));
+        assert_eq!(
+            "a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd",
+            hash(HashType::SHA1, Some(160)).unwrap().hex
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            hash(HashType::SHA1, Some(128)).unwrap_err().kind()
+        );
+        assert!(hash(HashType::BLAKE2S, Some(512)).is_err());
+        assert!(hash(HashType::BLAKE2B, Some(12)).is_err());
+    }
+
+    #[test]
+    fn hash_reader_keyed_hash() {
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        let hash = |hash_type, digest_length, key: &[u8]| {
+            hash_reader_keyed(
+                hash_type,
+                digest_length,
+                Some(key),
+                "data".as_bytes(),
+                cancellation_token.clone(),
+                None,
+            )
+        };
+        assert_eq!(
+            "fc22e569d005d4161689ef25494abfc2bd64d064c66292c58aa18ddc488d94dc\
+             e2597e3d17cbf04197d7bebfd0ac52b526e87ed4e4d58e5b08b99ae1bb3e7d90",
+            hash(HashType::BLAKE2B, None, b"key").unwrap().hex
+        );
+        assert_eq!(
+            "37ff3fbbc984a8066168bdadf1b01567",
+            hash(HashType::BLAKE2S, Some(128), b"key").unwrap().hex
+        );
+        assert_eq!(
+            "5031fe3d989c6d1537a013fa6e739da23463fdaec3b70137d828e36ace221bd0",
+            hash(HashType::SHA256, None, b"key").unwrap().hex
+        );
+        let blake3_key = [7u8; 32];
+        let blake3_keyed = hash(HashType::BLAKE3, None, &blake3_key).unwrap().hex;
+        assert_eq!(64, blake3_keyed.len());
+        assert_ne!(
+            hash_reader(
+                HashType::BLAKE3,
+                "data".as_bytes(),
+                cancellation_token.clone(),
+                None
+            )
+            .unwrap()
+            .hex,
+            blake3_keyed
+        );
+        assert_eq!(
+            blake3_keyed[..32],
+            hash(HashType::BLAKE3, Some(128), &blake3_key).unwrap().hex
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            hash(HashType::BLAKE3, None, b"key").unwrap_err().kind()
+        );
+        assert!(hash(HashType::MD5, None, b"key").is_err());
+        assert!(hash(HashType::BLAKE2B, None, b"").is_err());
+        assert!(hash(HashType::BLAKE2S, None, &[0u8; 33]).is_err());
+    }
+
+    #[test]
+    fn get_hash_line_xxhsum_format() {
+        let cancellation_token_source = CancellationTokenSource::new();
+        let cancellation_token = cancellation_token_source.token();
+        let line = get_hash_line(
+            HashType::XXH3_64,
+            None,
+            None,
+            HashFileFormat::HashSum,
+            "file",
+            "".as_bytes(),
+            cancellation_token.clone(),
+        )
+        .unwrap();
+        assert_eq!("XXH3_2d06800538d394c2  file\n", line);
+        let line = get_hash_line(
+            HashType::XXH128,
+            None,
+            None,
+            HashFileFormat::HashSum,
+            "file",
+            "".as_bytes(),
+            cancellation_token.clone(),
+        )
+        .unwrap();
+        assert_eq!("99aa06d3014798d86001c324468d497f  file\n", line);
+    }
+
+    #[test]
+    fn get_hash_type_from_str_sha3() {
+        assert_eq!(HashType::SHA3_256, get_hash_type_from_str("SHA3-256"));
+        let hash_type_str: &str = HashType::SHA3_256.into();
+        assert_eq!("SHA3-256", hash_type_str);
+    }
+
+    // size and age
+
+    #[test]
+    fn get_size_from_str_units() {
+        assert_eq!(Ok(1024), get_size_from_str("1024"));
+        assert_eq!(Ok(512), get_size_from_str("512B"));
+        assert_eq!(Ok(2_048), get_size_from_str("2K"));
+        assert_eq!(Ok(5_242_880), get_size_from_str("5m"));
+        assert_eq!(Ok(1_073_741_824), get_size_from_str("1G"));
+        assert!(get_size_from_str("1X").is_err());
+        assert!(get_size_from_str("G").is_err());
+    }
+
+    #[test]
+    fn get_sample_size_from_str_units() {
+        assert_eq!(Ok(SampleSize::Count(500)), get_sample_size_from_str("500"));
+        assert_eq!(Ok(SampleSize::Percent(1.0)), get_sample_size_from_str("1%"));
+        assert_eq!(
+            Ok(SampleSize::Percent(0.5)),
+            get_sample_size_from_str("0.5%")
+        );
+        assert!(get_sample_size_from_str("0").is_err());
+        assert!(get_sample_size_from_str("150%").is_err());
+        assert!(get_sample_size_from_str("%").is_err());
+    }
+
+    #[test]
+    fn get_time_from_age_str_units() {
+        let now = SystemTime::now();
+        let time = get_time_from_age_str("7d").unwrap();
+        let age = now.duration_since(time).unwrap_or_default().as_secs();
+        assert!((604_799..=604_801).contains(&age));
+        assert!(get_time_from_age_str("7").is_err());
+        assert!(get_time_from_age_str("7y").is_err());
+    }
+
+    // file list
+
+    #[test]
+    fn get_file_list_new_line_delimited() {
+        let file_list = get_file_list("file1\nsub/file2\r\n\n".as_bytes()).unwrap();
+        assert_eq!(
+            vec![PathBuf::from("file1"), PathBuf::from("sub/file2")],
+            file_list
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn get_file_list_ill_formed_unicode() {
+        use std::os::unix::ffi::OsStrExt;
+        let file_list = get_file_list(&b"file\xff\0file2\0"[..]).unwrap();
+        assert_eq!(
+            vec![
+                PathBuf::from(std::ffi::OsStr::from_bytes(b"file\xff")),
+                PathBuf::from("file2")
+            ],
+            file_list
+        );
+    }
+
+    #[test]
+    fn get_file_list_nul_delimited() {
+        let file_list = get_file_list("file 1\0file\n2\0".as_bytes()).unwrap();
+        assert_eq!(
+            vec![PathBuf::from("file 1"), PathBuf::from("file\n2")],
+            file_list
+        );
+    }
+
+    // config
+
+    #[test]
+    fn config_from_toml_str() {
+        let config = config::Config::from_toml_str(
+            "type = \"blake3\"\nsum = true\nignore = '\\.DS_Store$'\nexclude = [\"*.tmp\"]\nmin-size = \"1K\"\n",
+        )
+        .unwrap();
+        assert_eq!(Some(HashType::BLAKE3), config.get_hash_type().unwrap());
+        assert_eq!(Some(true), config.sum);
+        assert_eq!(Some("\\.DS_Store$".to_string()), config.ignore_pattern);
+        assert_eq!(Some(vec!["*.tmp".to_string()]), config.exclude);
+        let options = config.to_options(Path::new("dir")).unwrap();
+        assert_eq!(Some(HashFileFormat::HashSum), options.hash_file_format);
+        assert_eq!(Some(1024), options.min_size);
+    }
+
+    #[test]
+    fn config_from_toml_str_invalid() {
+        assert!(config::Config::from_toml_str("unknown = true").is_err());
+        let config = config::Config::from_toml_str("type = \"crc\"").unwrap();
+        assert!(config.get_hash_type().is_err());
+    }
+
+    #[test]
+    fn config_from_env_vars() {
+        let config = config::Config::from_env_vars(vec![
+            ("HSHCHK_TYPE".to_string(), "sha256".to_string()),
+            ("HSHCHK_EXTRA".to_string(), "yes".to_string()),
+            ("HSHCHK_EXCLUDE".to_string(), "*.tmp, *.bak".to_string()),
+            ("PATH".to_string(), "/bin".to_string()),
+        ])
+        .unwrap();
+        assert_eq!(Some(HashType::SHA256), config.get_hash_type().unwrap());
+        assert_eq!(Some(true), config.extra);
+        assert_eq!(
+            Some(vec!["*.tmp".to_string(), "*.bak".to_string()]),
+            config.exclude
+        );
+        assert!(config::Config::from_env_vars(vec![(
+            "HSHCHK_SUM".to_string(),
+            "maybe".to_string()
+        )])
+        .is_err());
+    }
+
+    #[test]
+    fn config_merge() {
+        let config = config::Config {
+            hash_type: Some("md5".to_string()),
+            extra: Some(true),
+            ..Default::default()
+        }
+        .merge(config::Config {
+            hash_type: Some("sha1".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(Some("sha1".to_string()), config.hash_type);
+        assert_eq!(Some(true), config.extra);
+    }
+
     // hash file
 
     #[test]
     fn hash_file_load_single() {
-        let file = test_shared::create_tmp_file("filename|0|hash");
+        let file = test_shared::create_tmp_file("filename|0|8D777F385D3DFEC8815D20F7496026DC");
         let mut hash_file = HashFile::new();
-        hash_file.load(&file);
+        hash_file.load(&file, HashType::MD5).unwrap();
         assert_eq!(1, hash_file.get_file_paths().len());
         let entry = hash_file.get_entry("filename").unwrap();
         assert_eq!(0, entry.size.unwrap());
-        assert_eq!("hash", entry.digest);
+        assert_eq!("8d777f385d3dfec8815d20f7496026dc", entry.digest);
         fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
 
+    #[test]
+    fn hash_file_load_xxhsum() {
+        let file = test_shared::create_tmp_file(
+            "XXH3_2D06800538D394C2  file1\nXXH3 (file (2)) = XXH3_2d06800538d394c2\n",
+        );
+        let mut hash_file = HashFile::new();
+        hash_file.load(&file, HashType::XXH3_64).unwrap();
+        assert_eq!(2, hash_file.get_file_paths().len());
+        assert_eq!(
+            "2d06800538d394c2",
+            hash_file.get_entry("file1").unwrap().digest
+        );
+        assert_eq!(
+            "2d06800538d394c2",
+            hash_file.get_entry("file (2)").unwrap().digest
+        );
+        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
+        let file =
+            test_shared::create_tmp_file("XXH128 (file3) = 99aa06d3014798d86001c324468d497f\n");
+        let mut hash_file = HashFile::new();
+        hash_file.load(&file, HashType::XXH128).unwrap();
+        assert_eq!(
+            "99aa06d3014798d86001c324468d497f",
+            hash_file.get_entry("file3").unwrap().digest
+        );
+        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
+    }
+
+    #[test]
+    fn hash_file_metadata() {
+        let dir = test_shared::create_tmp_dir();
+        let file = dir.join("hshchk.sha1");
+        let mut hash_file = HashFile::new();
+        hash_file.set_metadata(HashType::SHA1, HashFileFormat::HashCheck, &dir);
+        for (file_path, size) in &[("file1", 1), ("file2", 2)] {
+            hash_file.add_entry(HashFileEntry {
+                file_path: file_path.to_string(),
+                size: Some(*size),
+                digest: "a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd".into(),
+            });
+        }
+        hash_file.save(&file, HashType::SHA1, HashFileFormat::HashCheck);
+        let content = fs::read_to_string(&file).unwrap();
+        let header: Vec<&str> = content
+            .lines()
+            .take_while(|line| line.starts_with('#'))
+            .collect();
+        assert_eq!("# version: 1", header[0]);
+        assert!(header.contains(&"# format: hshchk"));
+        assert!(header.contains(&"# algorithm: SHA1"));
+        assert!(header.contains(&"# entries: 2"));
+        assert!(header.contains(&"# bytes: 3"));
+        let mut hash_file = HashFile::new();
+        hash_file.load(&file, HashType::SHA1).unwrap();
+        assert_eq!(2, hash_file.get_file_paths().len());
+        assert_eq!(Some("SHA1"), hash_file.get_header_value("algorithm"));
+        assert!(hash_file
+            .get_header_value("tool")
+            .unwrap()
+            .starts_with("hshchk "));
+        let load_modified = |content: String| {
+            fs::write(&file, content).unwrap();
+            let mut hash_file = HashFile::new();
+            hash_file.load(&file, HashType::SHA1).unwrap_err().kind()
+        };
+        let truncated_content = content.lines().collect::<Vec<_>>()[..header.len() + 1].join("\n");
+        assert_eq!(
+            ErrorKind::InvalidData,
+            load_modified(truncated_content + "\n")
+        );
+        assert_eq!(
+            ErrorKind::InvalidData,
+            load_modified(content.replace("file1|1|a17c", "file1|1|b17c"))
+        );
+        assert_eq!(
+            ErrorKind::InvalidData,
+            load_modified(content.replace("# version: 1", "# version: 2"))
+        );
+        fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    }
+
+    #[test]
+    fn hash_file_reader() {
+        let file = test_shared::create_tmp_file(
+            "# algorithm: MD5\nfile1|0|8d777f385d3dfec8815d20f7496026dc\n\n\
+             file2|0|8d777f385d3dfec8815d20f7496026dc\nfile3|0\n",
+        );
+        let mut hash_file_reader = HashFileReader::open(&file, HashType::MD5).unwrap();
+        assert_eq!(Some("MD5"), hash_file_reader.get_header_value("algorithm"));
+        let mut file_paths = Vec::new();
+        while let Some(file_entry) = hash_file_reader.next_entry().unwrap() {
+            file_paths.push(file_entry.file_path);
+        }
+        assert_eq!(vec!["file1", "file2"], file_paths);
+        let error = hash_file_reader.finish().unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Checksum file contains malformed lines (line 5: "));
+        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
+    }
+
+    #[test]
+    fn path_set() {
+        let mut path_set = PathSet::new();
+        path_set.insert("dir/file2");
+        path_set.insert("file1");
+        path_set.insert("file1");
+        path_set.seal();
+        assert!(path_set.contains("file1"));
+        assert!(path_set.contains("dir/file2"));
+        assert!(!path_set.contains("file2"));
+    }
+
+    #[test]
+    fn sample_reproducible() {
+        let items: Vec<u32> = (0..100).collect();
+        let mut sample1 = items.clone();
+        sample::sample(&mut sample1, 10, 42);
+        let mut sample2 = items.clone();
+        sample::sample(&mut sample2, 10, 42);
+        let mut sample3 = items.clone();
+        sample::sample(&mut sample3, 10, 43);
+        assert_eq!(10, sample1.len());
+        assert_eq!(sample1, sample2);
+        assert_ne!(sample1, sample3);
+        sample1.sort_unstable();
+        sample1.dedup();
+        assert_eq!(10, sample1.len());
+        let mut all = items.clone();
+        sample::sample(&mut all, 200, 42);
+        assert_eq!(100, all.len());
+    }
+
+    #[test]
+    fn hash_file_compression() {
+        assert_eq!(
+            HashFileCompression::Zstd,
+            get_hash_file_compression(Path::new("hshchk.sha256.zst"))
+        );
+        assert_eq!(
+            HashFileCompression::Gzip,
+            get_hash_file_compression(Path::new("SHA256SUMS.gz"))
+        );
+        assert_eq!(
+            HashFileCompression::None,
+            get_hash_file_compression(Path::new("hshchk.sha256"))
+        );
+        let dir = test_shared::create_tmp_dir();
+        for (file_name, magic) in &[
+            ("hshchk.sha1.gz", &[0x1f, 0x8b][..]),
+            ("hshchk.sha1.zst", &[0x28, 0xb5, 0x2f, 0xfd][..]),
+        ] {
+            let file = dir.join(file_name);
+            let mut hash_file = HashFile::new();
+            hash_file.add_entry(HashFileEntry {
+                file_path: "file".into(),
+                size: Some(4),
+                digest: "a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd".into(),
+            });
+            hash_file.save(&file, HashType::SHA1, HashFileFormat::HashCheck);
+            assert!(fs::read(&file).unwrap().starts_with(magic));
+            let mut hash_file = HashFile::new();
+            hash_file.load(&file, HashType::SHA1).unwrap();
+            let file_entry = hash_file.get_entry("file").unwrap();
+            assert_eq!(Some(4), file_entry.size);
+            assert_eq!(
+                "a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd",
+                file_entry.digest
+            );
+        }
+        let file = dir.join("hshchk.sha1.zst");
+        fs::write(&file, "file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n").unwrap();
+        let mut hash_file = HashFile::new();
+        assert!(hash_file.load(&file, HashType::SHA1).is_err());
+        fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    }
+
+    #[test]
+    fn hash_file_load_unsafe_paths() {
+        let file = test_shared::create_tmp_file(
+            "dir/../file|0|8d777f385d3dfec8815d20f7496026dc\n../file|0|8d777f385d3dfec8815d20f7496026dc\n/etc/passwd|0|8d777f385d3dfec8815d20f7496026dc\n\
+             C:\\Windows\\win.ini|0|8d777f385d3dfec8815d20f7496026dc\ndir/../../file|0|8d777f385d3dfec8815d20f7496026dc\nfi\0le|0|8d777f385d3dfec8815d20f7496026dc\n",
+        );
+        let mut hash_file = HashFile::new();
+        let error = hash_file.load(&file, HashType::MD5).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        let message = error.to_string();
+        assert!(message.starts_with("Checksum file contains unsafe paths (line 2: "));
+        assert!(!message.contains("line 1: "));
+        for (line_number, reason) in &[
+            (2, "escapes the base directory"),
+            (3, "absolute path"),
+            (4, "absolute path"),
+            (5, "escapes the base directory"),
+            (6, "contains a NUL character"),
+        ] {
+            let line_start = message.find(&format!("line {}: ", line_number)).unwrap();
+            let line_message = message[line_start..].split(';').next().unwrap();
+            assert!(line_message.trim_end_matches(").").ends_with(reason));
+        }
+        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
+    }
+
+    #[test]
+    fn hash_file_load_normalized_paths() {
+        let file = test_shared::create_tmp_file(
+            "dir/../file|0|8d777f385d3dfec8815d20f7496026dc\n./sub/./file|0|8d777f385d3dfec8815d20f7496026dc\n",
+        );
+        let mut hash_file = HashFile::new();
+        hash_file.load(&file, HashType::MD5).unwrap();
+        assert!(hash_file.get_entry("file").is_some());
+        assert!(hash_file
+            .get_entry(&Path::new("sub").join("file").to_string_lossy())
+            .is_some());
+        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
+        let file = test_shared::create_tmp_file("dir/..|0|8d777f385d3dfec8815d20f7496026dc\n");
+        let error = hash_file.load(&file, HashType::MD5).unwrap_err();
+        assert!(error.to_string().contains("refers to the base directory"));
+        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
+    }
+
+    #[test]
+    fn hash_file_load_hash_check_comment_like_paths() {
+        let file = test_shared::create_tmp_file(
+            "# version: 1\nfile|0|8d777f385d3dfec8815d20f7496026dc\n#file|0|8d777f385d3dfec8815d20f7496026dc\n",
+        );
+        let mut hash_file = HashFile::new();
+        hash_file.load(&file, HashType::MD5).unwrap();
+        assert_eq!(Some("1"), hash_file.get_header_value("version"));
+        assert!(hash_file.get_entry("file").is_some());
+        assert!(hash_file.get_entry("#file").is_some());
+        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
+    }
+
+    #[test]
+    fn hash_file_load_unreadable_file() {
+        let dir = test_shared::create_tmp_dir();
+        let mut hash_file = HashFile::new();
+        assert!(hash_file
+            .load(&dir.join("hshchk.sha1"), HashType::SHA1)
+            .is_err());
+        #[cfg(unix)]
+        assert!(hash_file.load(&dir, HashType::SHA1).is_err());
+        fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    }
+
     #[test]
     fn hash_file_load_multiple() {
-        let file = test_shared::create_tmp_file("filename1|1|hash1\r\nfilename2|2|hash2");
+        let file = test_shared::create_tmp_file(
+            "filename1|1|8d777f385d3dfec8815d20f7496026dc\r\nfilename2|2|098f6bcd4621d373cade4e832627b4f6",
+        );
         let mut hash_file = HashFile::new();
-        hash_file.load(&file);
+        hash_file.load(&file, HashType::MD5).unwrap();
         assert_eq!(2, hash_file.get_file_paths().len());
         let entry = hash_file.get_entry("filename1").unwrap();
         assert_eq!(1, entry.size.unwrap());
-        assert_eq!("hash1", entry.digest);
+        assert_eq!("8d777f385d3dfec8815d20f7496026dc", entry.digest);
         let entry = hash_file.get_entry("filename2").unwrap();
         assert_eq!(2, entry.size.unwrap());
-        assert_eq!("hash2", entry.digest);
+        assert_eq!("098f6bcd4621d373cade4e832627b4f6", entry.digest);
         fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
 
+    fn load_error(content: &str, hash_type: HashType) -> String {
+        let file = test_shared::create_tmp_file(content);
+        let mut hash_file = HashFile::new();
+        let error = hash_file.load(&file, hash_type).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
+        error.to_string()
+    }
+
     #[test]
     fn hash_file_load_failed_size() {
-        let file = test_shared::create_tmp_file("filename|size|hash");
-        let file_clone = file.clone();
-        let mut hash_file = HashFile::new();
         assert_eq!(
-            std::panic::catch_unwind(move || {
-                hash_file.load(&file_clone);
-            })
-            .err()
-            .and_then(|a| a
-                .downcast_ref::<String>()
-                .map(|s| { &s[..25] == "Failed to parse file size" })),
-            Some(true)
+            "Checksum file contains malformed lines (line 1: invalid file size \"size\").",
+            load_error(
+                "filename|size|8d777f385d3dfec8815d20f7496026dc",
+                HashType::MD5
+            )
         );
-        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
 
     #[test]
     fn hash_file_load_failed_filename() {
-        let file = test_shared::create_tmp_file(&("a".repeat(4096) + "|0|hash"));
-        let file_clone = file.clone();
-        let mut hash_file = HashFile::new();
         assert_eq!(
-            std::panic::catch_unwind(move || {
-                hash_file.load(&file_clone);
-            })
-            .err()
-            .and_then(|a| a
-                .downcast_ref::<String>()
-                .map(|s| { s == "File path length must be less than 4096 characters." })),
-            Some(true)
+            "Checksum file contains malformed lines \
+             (line 1: file path length must be less than 4096 characters).",
+            load_error(
+                &("a".repeat(4096) + "|0|8d777f385d3dfec8815d20f7496026dc"),
+                HashType::MD5
+            )
         );
-        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
 
     #[test]
     fn hash_file_load_failed_hash() {
-        let file = test_shared::create_tmp_file(&(String::from("filename|0|") + &"a".repeat(1025)));
-        let file_clone = file.clone();
+        assert_eq!(
+            "Checksum file contains malformed lines \
+             (line 1: MD5 digest length must be 128 bits, found 4100).",
+            load_error(
+                &(String::from("filename|0|") + &"a".repeat(1025)),
+                HashType::MD5
+            )
+        );
+    }
+
+    #[test]
+    fn hash_file_load_sfv() {
+        let dir = test_shared::create_tmp_dir();
+        let file = test_shared::create_file_with_content(
+            &dir,
+            "files.sfv",
+            "; Generated by cksfv\r\nfile with spaces ADF3F363\r\nfile D87F7E0C\r\n",
+        );
         let mut hash_file = HashFile::new();
+        hash_file.load(&file, HashType::CRC32).unwrap();
         assert_eq!(
-            std::panic::catch_unwind(move || {
-                hash_file.load(&file_clone);
-            })
-            .err()
-            .and_then(|a| a
-                .downcast_ref::<String>()
-                .map(|s| { s == "Hash length must be less than 1025 characters." })),
-            Some(true)
+            "adf3f363",
+            hash_file.get_entry("file with spaces").unwrap().digest
+        );
+        assert_eq!("d87f7e0c", hash_file.get_entry("file").unwrap().digest);
+        fs::write(&file, "file\n").unwrap();
+        assert!(hash_file.load(&file, HashType::CRC32).is_err());
+        fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    }
+
+    #[test]
+    fn hash_file_load_failed_digests() {
+        assert_eq!(
+            "Checksum file contains malformed lines (\
+             line 2: digest \"8d777f385d3dfec8815d20f7496026dg\" is not hexadecimal; \
+             line 3: MD5 digest length must be 128 bits, found 112; \
+             line 4: expected `path|size|digest`).",
+            load_error(
+                "file1|0|8d777f385d3dfec8815d20f7496026dc\n\
+                 file2|0|8d777f385d3dfec8815d20f7496026dg\n\
+                 file3|0|8d777f385d3dfec8815d20f74960\n\
+                 file4\n",
+                HashType::MD5
+            )
+        );
+        assert_eq!(
+            "Checksum file contains malformed lines (\
+             line 2: BLAKE2B digest length must be 256 bits, found 512; \
+             line 3: expected `digest  path`).",
+            load_error(
+                &format!(
+                    "{}  file1\n{}  file2\n{}\n",
+                    "a".repeat(64),
+                    "a".repeat(128),
+                    "a".repeat(64)
+                ),
+                HashType::BLAKE2B
+            )
+        );
+        assert_eq!(
+            "Checksum file contains malformed lines (\
+             line 1: unsupported BLAKE2S digest length: 512 bits).",
+            load_error(&format!("{}  file\n", "a".repeat(128)), HashType::BLAKE2S)
         );
-        fs::remove_dir_all(file.parent().unwrap()).expect("Failed to remove test directory.");
     }
 
     #[test]
@@ -290,7 +1364,6 @@ mod tests {
         hash_file.add_entry(HashFileEntry {
             file_path: "filename".into(),
             size: None,
-            binary: false,
             digest: "hash".into(),
         });
         assert!(!hash_file.is_empty());
@@ -302,13 +1375,11 @@ mod tests {
         hash_file.add_entry(HashFileEntry {
             file_path: "filename1".into(),
             size: None,
-            binary: false,
             digest: "hash1".into(),
         });
         hash_file.add_entry(HashFileEntry {
             file_path: "filename2".into(),
             size: None,
-            binary: false,
             digest: "hash2".into(),
         });
         let mut filenames = hash_file.get_file_paths();
@@ -322,10 +1393,89 @@ mod tests {
         hash_file.add_entry(HashFileEntry {
             file_path: "filename".into(),
             size: None,
-            binary: false,
             digest: "hash".into(),
         });
         hash_file.remove_entry("filename");
         assert!(hash_file.is_empty());
     }
+
+    // signature
+
+    static SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQBAgMEBQYHCB5YqhSZcQMyy8Cs86WTnW/kQMibIH5fusA9J/DB+PK85QDDtf25eGiWb1WKQRnzgnpOCpTTZYUjfj378ad5/gE=
+trusted comment: timestamp:1700000000\tfile:hshchk.sha1\thashed
+gwo9TJQSdyNnH75R7MQrFBZk6vA/47oCPRzj1DyWV0x2DP7N4EKM//CoWIvdhuBCrzBMNsmz+dq49mS2plIvAg==
+";
+
+    fn no_password() -> std::io::Result<String> {
+        panic!("Unexpected password request.")
+    }
+
+    #[test]
+    fn signature_secret_key_decode() {
+        let secret_key = SecretKey::decode(test_shared::MINISIGN_SECRET_KEY, no_password).unwrap();
+        let public_key = secret_key.get_public_key();
+        assert_eq!("0807060504030201", public_key.get_key_id());
+        assert_eq!(
+            format!(
+                "untrusted comment: minisign public key 0807060504030201\n{}\n",
+                test_shared::MINISIGN_PUBLIC_KEY
+            ),
+            public_key.encode()
+        );
+        let secret_key = SecretKey::decode(test_shared::MINISIGN_ENCRYPTED_SECRET_KEY, || {
+            Ok("password".into())
+        })
+        .unwrap();
+        assert_eq!(
+            test_shared::MINISIGN_PUBLIC_KEY,
+            secret_key.get_public_key().encode().lines().nth(1).unwrap()
+        );
+        assert!(
+            SecretKey::decode(test_shared::MINISIGN_ENCRYPTED_SECRET_KEY, || Ok(
+                "wrong".into()
+            ))
+            .is_err()
+        );
+        assert!(SecretKey::decode(test_shared::MINISIGN_PUBLIC_KEY, no_password).is_err());
+    }
+
+    #[test]
+    fn signature_sign() {
+        let secret_key = SecretKey::decode(test_shared::MINISIGN_SECRET_KEY, no_password).unwrap();
+        let signature = secret_key.sign(b"data", "timestamp:1700000000\tfile:hshchk.sha1\thashed");
+        assert_eq!(SIGNATURE, signature);
+        // Signatures must be verifiable without hshchk.
+        let public_key =
+            minisign_verify::PublicKey::from_base64(test_shared::MINISIGN_PUBLIC_KEY).unwrap();
+        let signature = minisign_verify::Signature::decode(&signature).unwrap();
+        assert!(public_key.verify(b"data", &signature, false).is_ok());
+        assert!(public_key.verify(b"other data", &signature, false).is_err());
+    }
+
+    #[test]
+    fn signature_verify() {
+        let public_key = PublicKey::decode(test_shared::MINISIGN_PUBLIC_KEY).unwrap();
+        assert_eq!(
+            "timestamp:1700000000\tfile:hshchk.sha1\thashed",
+            public_key.verify(b"data", SIGNATURE).unwrap()
+        );
+        assert!(public_key.verify(b"other data", SIGNATURE).is_err());
+        let tampered_signature = SIGNATURE.replace("1700000000", "1800000000");
+        assert!(public_key.verify(b"data", &tampered_signature).is_err());
+        // Signature created with minisign, without prehashing.
+        let public_key = PublicKey::decode(
+            "untrusted comment: minisign public key E7620F1842B4E81F
+RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3",
+        )
+        .unwrap();
+        let signature = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==
+";
+        assert!(public_key.verify(b"test", signature).is_ok());
+        let other_public_key = PublicKey::decode(test_shared::MINISIGN_PUBLIC_KEY).unwrap();
+        assert!(other_public_key.verify(b"test", signature).is_err());
+    }
 }
@@ -1,6 +1,5 @@
 use num_format::{Locale, ToFormattedString};
 use std::io::{stdout, Write};
-use std::iter::repeat;
 use std::time::Instant;
 use unicode_segmentation::UnicodeSegmentation;
 
@@ -9,6 +8,7 @@ use crate::speed::get_speed;
 use crate::tty::terminal_size;
 
 const OUTPUT_REFRESH_IN_MILLIS: u32 = 233;
+const DEFAULT_OUTPUT_WIDTH: u16 = 80;
 
 pub struct Output {
     output_width: usize,
@@ -19,9 +19,10 @@ pub struct Output {
 
 impl Output {
     pub fn new() -> Self {
-        let (output_width, _) = terminal_size().unwrap();
+        // Fall back to a default width when stdout isn't a terminal (e.g. redirected).
+        let output_width = terminal_size().map_or(DEFAULT_OUTPUT_WIDTH, |(width, _)| width.0);
         Output {
-            output_width: (output_width.0 - 1) as usize,
+            output_width: output_width.saturating_sub(1) as usize,
             refresh_rate_in_millis: OUTPUT_REFRESH_IN_MILLIS,
             last_output_instant: None,
             last_output_file_progress: FileProgress {
@@ -34,7 +35,7 @@ impl Output {
         let line_len = line.graphemes(true).count();
         if line_len < self.output_width {
             let gap = self.output_width - line_len;
-            let pad = &repeat(" ").take(gap).collect::<String>();
+            let pad = &" ".repeat(gap);
             padded_line = line + pad;
         }
 
@@ -59,42 +60,41 @@ impl Output {
             let mut info_output = String::new();
             if error {
                 info_output = format!(" => {}", info);
-            } else if self.last_output_file_progress.file_path == file_path {
-                if bytes_processed != self.last_output_file_progress.bytes_processed {
-                    let percent = match file_size {
-                        0 => 100,
-                        _ => bytes_processed * 100 / file_size,
-                    };
-                    let speed = get_speed(
-                        bytes_processed,
-                        self.last_output_file_progress.bytes_processed,
-                        ellapsed_millis,
-                    );
+            } else if self.last_output_file_progress.file_path == file_path
+                && bytes_processed != self.last_output_file_progress.bytes_processed
+            {
+                let percent = match file_size {
+                    0 => 100,
+                    _ => bytes_processed * 100 / file_size,
+                };
+                let speed = get_speed(
+                    bytes_processed,
+                    self.last_output_file_progress.bytes_processed,
+                    ellapsed_millis,
+                );
 
-                    info_output = format!(
-                        " ({}; {} %; {} {})",
-                        file_size.to_formatted_string(&Locale::en),
-                        percent.to_formatted_string(&Locale::en),
-                        speed.bytes_per_interval.to_formatted_string(&Locale::en),
-                        speed.unit
-                    );
-                }
+                info_output = format!(
+                    " ({}; {} %; {} {})",
+                    file_size.to_formatted_string(&Locale::en),
+                    percent.to_formatted_string(&Locale::en),
+                    speed.bytes_per_interval.to_formatted_string(&Locale::en),
+                    speed.unit
+                );
             }
 
-            let printed_file_path: String;
-            let file_path_max_size = self.output_width - info_output.len();
+            let file_path_max_size = self.output_width.saturating_sub(info_output.len());
             let mut file_path_graphemes = file_path.graphemes(true);
             let file_path_len = file_path_graphemes.clone().count();
-            if file_path_max_size < file_path_len {
+            let printed_file_path = if file_path_max_size < file_path_len {
                 let offset = file_path_len - file_path_max_size + "..".len();
                 for _ in 0..offset {
                     file_path_graphemes.next();
                 }
 
-                printed_file_path = format!("{}{}", "..", file_path_graphemes.as_str());
+                format!("{}{}", "..", file_path_graphemes.as_str())
             } else {
-                printed_file_path = file_path.to_owned();
-            }
+                file_path.to_owned()
+            };
 
             let line_output = self.pad_line(format!("{}{}", printed_file_path, info_output));
             if error {
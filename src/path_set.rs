@@ -0,0 +1,32 @@
+use xxhash_rust::xxh3::xxh3_64;
+
+// A compact set of file paths, storing a 64-bit hash per path instead of the path
+// itself. A hash collision can make an absent path appear present, which is an
+// acceptable trade-off for detecting extra files of very large checksum files.
+#[derive(Default)]
+pub struct PathSet {
+    hashes: Vec<u64>,
+}
+
+impl PathSet {
+    pub fn new() -> Self {
+        PathSet::default()
+    }
+
+    pub fn insert(&mut self, file_path: &str) {
+        self.hashes.push(xxh3_64(file_path.as_bytes()));
+    }
+
+    // Must be called once all paths are inserted, before looking them up.
+    pub fn seal(&mut self) {
+        self.hashes.sort_unstable();
+        self.hashes.dedup();
+        self.hashes.shrink_to_fit();
+    }
+
+    pub fn contains(&self, file_path: &str) -> bool {
+        self.hashes
+            .binary_search(&xxh3_64(file_path.as_bytes()))
+            .is_ok()
+    }
+}
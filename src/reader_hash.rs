@@ -0,0 +1,96 @@
+use crate::block_hasher::{BlockHasher, HashProgress};
+use crossbeam::channel::Sender;
+use digest::Digest;
+use std::io::{BufReader, Read, Result};
+
+// Hash functions usable by `ReaderHash`. Implemented for all `Digest` types
+// and for hash functions whose digest length is chosen at runtime.
+pub trait ReaderDigest {
+    fn update(&mut self, data: &[u8]);
+    fn finalize_hex(&mut self) -> String;
+}
+
+impl<T: Digest> ReaderDigest for T {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+    fn finalize_hex(&mut self) -> String {
+        hex::encode(self.finalize_reset())
+    }
+}
+
+pub struct ReaderHash<T: ReaderDigest, R: Read> {
+    reader: BufReader<R>,
+    hasher: T,
+    buffer: Vec<u8>,
+    buffer_size: usize,
+    bytes_processed_event: Option<Sender<HashProgress>>,
+    bytes_processed_notification_block_size: u64,
+}
+
+const DEFAULT_BUFFER_SIZE: usize = 1_048_576;
+const DEFAULT_BYTES_PROCESSED_NOTIFICATION_BLOCK_SIZE: u64 = 2_097_152;
+
+impl<T: ReaderDigest, R: Read> ReaderHash<T, R> {
+    pub fn new_with_hasher_and_buffer_size(reader: R, hasher: T, buffer_size: usize) -> Self {
+        ReaderHash {
+            reader: BufReader::new(reader),
+            hasher,
+            buffer: Vec::with_capacity(buffer_size),
+            buffer_size,
+            bytes_processed_event: None,
+            bytes_processed_notification_block_size: 0,
+        }
+    }
+    pub fn new_with_hasher(reader: R, hasher: T) -> Self {
+        ReaderHash::new_with_hasher_and_buffer_size(reader, hasher, DEFAULT_BUFFER_SIZE)
+    }
+}
+
+impl<T: ReaderDigest + Default, R: Read> ReaderHash<T, R> {
+    pub fn new_with_buffer_size(reader: R, buffer_size: usize) -> Self {
+        ReaderHash::new_with_hasher_and_buffer_size(reader, T::default(), buffer_size)
+    }
+    pub fn new(reader: R) -> Self {
+        ReaderHash::new_with_buffer_size(reader, DEFAULT_BUFFER_SIZE)
+    }
+}
+
+impl<T: ReaderDigest, R: Read> BlockHasher for ReaderHash<T, R> {
+    fn read(&mut self) -> Result<usize> {
+        self.buffer.clear();
+        let mut adaptor = (&mut self.reader).take(self.buffer_size as u64);
+        adaptor.read_to_end(&mut self.buffer)
+    }
+    fn update(&mut self, byte_count: usize) {
+        self.hasher.update(&self.buffer[..byte_count]);
+    }
+    fn digest(&mut self) -> String {
+        self.hasher.finalize_hex()
+    }
+    fn set_bytes_processed_event_sender(&mut self, sender: Sender<HashProgress>) {
+        self.set_bytes_processed_event_sender_with_bytes_processed_notification_block_size(
+            sender,
+            DEFAULT_BYTES_PROCESSED_NOTIFICATION_BLOCK_SIZE,
+        )
+    }
+    fn set_bytes_processed_event_sender_with_bytes_processed_notification_block_size(
+        &mut self,
+        sender: Sender<HashProgress>,
+        bytes_processed_notification_block_size: u64,
+    ) {
+        self.bytes_processed_event = Some(sender);
+        self.bytes_processed_notification_block_size = bytes_processed_notification_block_size;
+    }
+    fn is_bytes_processed_event_sender_defined(&self) -> bool {
+        self.bytes_processed_event.is_some()
+    }
+    fn bytes_processed_notification_block_size(&self) -> u64 {
+        self.bytes_processed_notification_block_size
+    }
+    fn handle_bytes_processed_event(&self, args: HashProgress) {
+        if let Some(sender) = &self.bytes_processed_event {
+            sender.send(args).unwrap();
+        }
+    }
+}
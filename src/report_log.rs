@@ -0,0 +1,85 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::hash_file_process::{
+    FileProcessEntry, HashFileProcessResult, HashFileProcessStats, HashFileProcessType,
+};
+
+// Appends timestamped process events to a log file, leaving an audit trail
+// regardless of what is written to the terminal.
+pub struct ReportLog {
+    writer: BufWriter<File>,
+}
+
+impl ReportLog {
+    pub fn open(path: &Path) -> Result<ReportLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(ReportLog {
+            writer: BufWriter::new(file),
+        })
+    }
+    pub fn write_start(
+        &mut self,
+        process_type: HashFileProcessType,
+        base_path: &Path,
+    ) -> Result<()> {
+        self.write_line(&format!(
+            "{:?} started: {}",
+            process_type,
+            base_path.display()
+        ))
+    }
+    pub fn write_error(&mut self, file_process_entry: &FileProcessEntry) -> Result<()> {
+        self.write_entry("ERROR", file_process_entry)
+    }
+    pub fn write_warning(&mut self, file_process_entry: &FileProcessEntry) -> Result<()> {
+        self.write_entry("WARNING", file_process_entry)
+    }
+    pub fn write_result(
+        &mut self,
+        process_type: HashFileProcessType,
+        result: HashFileProcessResult,
+        stats: &HashFileProcessStats,
+    ) -> Result<()> {
+        let mut line = format!(
+            "{:?} result: {:?} (files processed: {}, bytes processed: {}, mismatches: {}, \
+             missing: {}, errors: {}, warnings: {}",
+            process_type,
+            result,
+            stats.files_processed,
+            stats.bytes_processed,
+            stats.mismatches,
+            stats.missing,
+            stats.errors,
+            stats.warnings
+        );
+        if let Some(sample_seed) = stats.sample_seed {
+            line.push_str(&format!(
+                ", sampled: {} of {}, seed: {}",
+                stats.sampled, stats.sample_population, sample_seed
+            ));
+        }
+
+        line.push(')');
+        self.write_line(&line)?;
+        self.writer.flush()
+    }
+    fn write_entry(&mut self, level: &str, file_process_entry: &FileProcessEntry) -> Result<()> {
+        self.write_line(&format!(
+            "{} {} => {:?}",
+            level,
+            file_process_entry.file_path.display(),
+            file_process_entry.state
+        ))
+    }
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        writeln!(
+            self.writer,
+            "{} {}",
+            humantime::format_rfc3339_seconds(SystemTime::now()),
+            line
+        )
+    }
+}
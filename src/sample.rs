@@ -0,0 +1,36 @@
+// splitmix64, a small generator whose output only depends on its seed, so that a
+// sample can be reproduced on any platform by giving the same seed.
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Returns a number in `0..bound`.
+    pub fn next_below(&mut self, bound: u64) -> u64 {
+        ((u128::from(self.next_u64()) * u128::from(bound)) >> 64) as u64
+    }
+}
+
+// Keeps `count` items picked at random, using a partial Fisher-Yates shuffle.
+pub fn sample<T>(items: &mut Vec<T>, count: usize, seed: u64) {
+    let mut rng = SplitMix64::new(seed);
+    let count = count.min(items.len());
+    for index in 0..count {
+        let picked = index + rng.next_below((items.len() - index) as u64) as usize;
+        items.swap(index, picked);
+    }
+
+    items.truncate(count);
+}
@@ -0,0 +1,304 @@
+use blake2::{Blake2b, Digest, VarBlake2b};
+use digest::{Update, VariableOutput};
+use ed25519_dalek::{ExpandedSecretKey, Signature};
+use scrypt::ScryptParams;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Detached signatures use the minisign format (https://jedisct1.github.io/minisign/),
+// so that they can be verified with `minisign -V -p <public key> -m <checksum file>`.
+
+const SIGNATURE_FILE_EXTENSION: &str = "minisig";
+const UNTRUSTED_COMMENT_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_COMMENT_PREFIX: &str = "trusted comment: ";
+const SIGNATURE_ALGORITHM: &[u8; 2] = b"Ed";
+const PREHASHED_SIGNATURE_ALGORITHM: &[u8; 2] = b"ED";
+const SCRYPT_KDF_ALGORITHM: &[u8; 2] = b"Sc";
+const NO_KDF_ALGORITHM: &[u8; 2] = &[0, 0];
+const BLAKE2B_CHECKSUM_ALGORITHM: &[u8; 2] = b"B2";
+const KEY_ID_LENGTH: usize = 8;
+const PUBLIC_KEY_LENGTH: usize = 2 + KEY_ID_LENGTH + ed25519_dalek::PUBLIC_KEY_LENGTH;
+const SIGNATURE_LENGTH: usize = 2 + KEY_ID_LENGTH + ed25519_dalek::SIGNATURE_LENGTH;
+const KDF_SALT_LENGTH: usize = 32;
+const CHECKSUM_LENGTH: usize = 32;
+// Key ID, Ed25519 keypair and checksum, encrypted as a whole.
+const SECRET_KEY_DATA_LENGTH: usize =
+    KEY_ID_LENGTH + ed25519_dalek::KEYPAIR_LENGTH + CHECKSUM_LENGTH;
+const SECRET_KEY_LENGTH: usize = 6 + KDF_SALT_LENGTH + 16 + SECRET_KEY_DATA_LENGTH;
+
+pub struct PublicKey {
+    key_id: [u8; KEY_ID_LENGTH],
+    key: ed25519_dalek::PublicKey,
+}
+
+impl PublicKey {
+    // Accepts the content of a minisign public key file or the base64 encoded key alone.
+    pub fn decode(content: &str) -> Result<Self> {
+        let bytes = decode_base64_line(content, "public key")?;
+        if bytes.len() != PUBLIC_KEY_LENGTH || &bytes[..2] != SIGNATURE_ALGORITHM {
+            return Err(invalid_data("Invalid public key."));
+        }
+
+        let mut key_id = [0u8; KEY_ID_LENGTH];
+        key_id.copy_from_slice(&bytes[2..2 + KEY_ID_LENGTH]);
+        let key = ed25519_dalek::PublicKey::from_bytes(&bytes[2 + KEY_ID_LENGTH..])
+            .map_err(|_| invalid_data("Invalid public key."))?;
+        Ok(PublicKey { key_id, key })
+    }
+    pub fn from_file(file_path: &Path) -> Result<Self> {
+        PublicKey::decode(&fs::read_to_string(file_path)?)
+    }
+    pub fn get_key_id(&self) -> String {
+        format_key_id(&self.key_id)
+    }
+    pub fn encode(&self) -> String {
+        let mut bytes = SIGNATURE_ALGORITHM.to_vec();
+        bytes.extend_from_slice(&self.key_id);
+        bytes.extend_from_slice(self.key.as_bytes());
+        format!(
+            "{}minisign public key {}\n{}\n",
+            UNTRUSTED_COMMENT_PREFIX,
+            self.get_key_id(),
+            base64::encode(bytes)
+        )
+    }
+    // Verifies `signature` (the content of a signature file) against `data` and
+    // returns its trusted comment.
+    pub fn verify(&self, data: &[u8], signature: &str) -> Result<String> {
+        let mut lines = signature.lines();
+        let (signature_line, trusted_comment_line, global_signature_line) =
+            match (lines.next(), lines.next(), lines.next(), lines.next()) {
+                (Some(untrusted_comment), Some(signature), Some(trusted_comment), Some(global))
+                    if untrusted_comment.starts_with(UNTRUSTED_COMMENT_PREFIX) =>
+                {
+                    (signature, trusted_comment, global)
+                }
+                _ => return Err(invalid_data("Invalid signature.")),
+            };
+        let trusted_comment = trusted_comment_line
+            .strip_prefix(TRUSTED_COMMENT_PREFIX)
+            .ok_or_else(|| invalid_data("Invalid signature."))?;
+        let signature_bytes = base64::decode(signature_line.trim())
+            .map_err(|_| invalid_data("Invalid signature."))?;
+        if signature_bytes.len() != SIGNATURE_LENGTH {
+            return Err(invalid_data("Invalid signature."));
+        }
+
+        let key_id = &signature_bytes[2..2 + KEY_ID_LENGTH];
+        if key_id != self.key_id {
+            return Err(invalid_data(&format!(
+                "Signature was made with a different key (ID {}).",
+                format_key_id(key_id)
+            )));
+        }
+
+        let signature = &signature_bytes[2 + KEY_ID_LENGTH..];
+        let message = match &signature_bytes[..2] {
+            algorithm if algorithm == PREHASHED_SIGNATURE_ALGORITHM => {
+                Blake2b::digest(data).to_vec()
+            }
+            algorithm if algorithm == SIGNATURE_ALGORITHM => data.to_vec(),
+            _ => return Err(invalid_data("Unsupported signature algorithm.")),
+        };
+        self.verify_signature(&message, signature)?;
+
+        // The global signature covers the trusted comment.
+        let global_signature = base64::decode(global_signature_line.trim())
+            .map_err(|_| invalid_data("Invalid signature."))?;
+        let mut global_message = signature.to_vec();
+        global_message.extend_from_slice(trusted_comment.as_bytes());
+        self.verify_signature(&global_message, &global_signature)?;
+
+        Ok(trusted_comment.to_string())
+    }
+    fn verify_signature(&self, message: &[u8], signature: &[u8]) -> Result<()> {
+        let signature =
+            Signature::try_from(signature).map_err(|_| invalid_data("Invalid signature."))?;
+        self.key
+            .verify_strict(message, &signature)
+            .map_err(|_| invalid_data("Signature verification failed."))
+    }
+}
+
+pub struct SecretKey {
+    key_id: [u8; KEY_ID_LENGTH],
+    secret: ed25519_dalek::SecretKey,
+    public: ed25519_dalek::PublicKey,
+}
+
+impl SecretKey {
+    // Accepts the content of a minisign secret key file. `get_password` is only
+    // called for encrypted keys.
+    pub fn decode<F: FnOnce() -> Result<String>>(content: &str, get_password: F) -> Result<Self> {
+        let bytes = decode_base64_line(content, "secret key")?;
+        if bytes.len() != SECRET_KEY_LENGTH
+            || &bytes[..2] != SIGNATURE_ALGORITHM
+            || &bytes[4..6] != BLAKE2B_CHECKSUM_ALGORITHM
+        {
+            return Err(invalid_data("Invalid secret key."));
+        }
+
+        let salt = &bytes[6..6 + KDF_SALT_LENGTH];
+        let opslimit = read_u64_le(&bytes[38..46]);
+        let memlimit = read_u64_le(&bytes[46..54]);
+        let mut data = bytes[54..].to_vec();
+        match &bytes[2..4] {
+            algorithm if algorithm == SCRYPT_KDF_ALGORITHM => {
+                let password = get_password()?;
+                let mut stream = [0u8; SECRET_KEY_DATA_LENGTH];
+                scrypt::scrypt(
+                    password.as_bytes(),
+                    salt,
+                    &get_scrypt_params(opslimit, memlimit)?,
+                    &mut stream,
+                )
+                .map_err(|_| invalid_data("Invalid secret key."))?;
+                data.iter_mut()
+                    .zip(stream.iter())
+                    .for_each(|(byte, stream_byte)| *byte ^= stream_byte);
+            }
+            algorithm if algorithm == NO_KDF_ALGORITHM => {}
+            _ => return Err(invalid_data("Unsupported secret key encryption.")),
+        }
+
+        let mut key_id = [0u8; KEY_ID_LENGTH];
+        key_id.copy_from_slice(&data[..KEY_ID_LENGTH]);
+        let keypair = &data[KEY_ID_LENGTH..KEY_ID_LENGTH + ed25519_dalek::KEYPAIR_LENGTH];
+        let checksum = &data[KEY_ID_LENGTH + ed25519_dalek::KEYPAIR_LENGTH..];
+        if get_secret_key_checksum(&key_id, keypair) != checksum {
+            return Err(invalid_data("Incorrect password or corrupted secret key."));
+        }
+
+        let secret =
+            ed25519_dalek::SecretKey::from_bytes(&keypair[..ed25519_dalek::SECRET_KEY_LENGTH])
+                .map_err(|_| invalid_data("Invalid secret key."))?;
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        if public.as_bytes() != &keypair[ed25519_dalek::SECRET_KEY_LENGTH..] {
+            return Err(invalid_data("Invalid secret key."));
+        }
+
+        Ok(SecretKey {
+            key_id,
+            secret,
+            public,
+        })
+    }
+    pub fn from_file<F: FnOnce() -> Result<String>>(
+        file_path: &Path,
+        get_password: F,
+    ) -> Result<Self> {
+        SecretKey::decode(&fs::read_to_string(file_path)?, get_password)
+    }
+    pub fn get_public_key(&self) -> PublicKey {
+        PublicKey {
+            key_id: self.key_id,
+            key: self.public,
+        }
+    }
+    // Returns the content of a signature file for `data`.
+    pub fn sign(&self, data: &[u8], trusted_comment: &str) -> String {
+        let expanded_secret = ExpandedSecretKey::from(&self.secret);
+        let signature = expanded_secret
+            .sign(&Blake2b::digest(data), &self.public)
+            .to_bytes();
+        let mut signature_bytes = PREHASHED_SIGNATURE_ALGORITHM.to_vec();
+        signature_bytes.extend_from_slice(&self.key_id);
+        signature_bytes.extend_from_slice(&signature);
+        let mut global_message = signature.to_vec();
+        global_message.extend_from_slice(trusted_comment.as_bytes());
+        let global_signature = expanded_secret.sign(&global_message, &self.public);
+        format!(
+            "{}signature from minisign secret key\n{}\n{}{}\n{}\n",
+            UNTRUSTED_COMMENT_PREFIX,
+            base64::encode(signature_bytes),
+            TRUSTED_COMMENT_PREFIX,
+            trusted_comment,
+            base64::encode(global_signature.to_bytes())
+        )
+    }
+}
+
+pub fn get_signature_path(file_path: &Path) -> PathBuf {
+    let mut signature_path = file_path.as_os_str().to_owned();
+    signature_path.push(".");
+    signature_path.push(SIGNATURE_FILE_EXTENSION);
+    PathBuf::from(signature_path)
+}
+
+// Writes a detached signature next to `file_path` and returns its path.
+pub fn sign_file(file_path: &Path, secret_key: &SecretKey) -> Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+    let trusted_comment = format!("timestamp:{}\tfile:{}\thashed", timestamp, file_name);
+    let signature = secret_key.sign(&fs::read(file_path)?, &trusted_comment);
+    let signature_path = get_signature_path(file_path);
+    fs::write(&signature_path, signature)?;
+    Ok(signature_path)
+}
+
+pub fn verify_file(file_path: &Path, public_key: &PublicKey) -> Result<String> {
+    let signature = fs::read_to_string(get_signature_path(file_path))?;
+    public_key.verify(&fs::read(file_path)?, &signature)
+}
+
+fn decode_base64_line(content: &str, name: &str) -> Result<Vec<u8>> {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_COMMENT_PREFIX))
+        .ok_or_else(|| invalid_data(&format!("Invalid {}.", name)))?;
+    base64::decode(line).map_err(|_| invalid_data(&format!("Invalid {}.", name)))
+}
+
+fn get_secret_key_checksum(key_id: &[u8], keypair: &[u8]) -> Vec<u8> {
+    let mut hasher = VarBlake2b::new(CHECKSUM_LENGTH).unwrap();
+    hasher.update(SIGNATURE_ALGORITHM);
+    hasher.update(key_id);
+    hasher.update(keypair);
+    hasher.finalize_boxed().into_vec()
+}
+
+// Same derivation of scrypt parameters from libsodium limits as minisign.
+fn get_scrypt_params(opslimit: u64, memlimit: u64) -> Result<ScryptParams> {
+    let opslimit = opslimit.max(32_768);
+    let r = 8u64;
+    let get_log_n = |max_n: u64| {
+        (1..63)
+            .find(|log_n| 1u64 << log_n > max_n / 2)
+            .unwrap_or(63)
+    };
+    let (log_n, p) = if opslimit < memlimit / 32 {
+        (get_log_n(opslimit / (r * 4)), 1)
+    } else {
+        let log_n = get_log_n(memlimit / (r * 128));
+        let max_rp = ((opslimit / 4) >> log_n).min(0x3fff_ffff);
+        (log_n, max_rp / r)
+    };
+    ScryptParams::new(log_n as u8, r as u32, p as u32)
+        .map_err(|_| invalid_data("Unsupported secret key encryption parameters."))
+}
+
+fn read_u64_le(bytes: &[u8]) -> u64 {
+    let mut value = [0u8; 8];
+    value.copy_from_slice(bytes);
+    u64::from_le_bytes(value)
+}
+
+// Key IDs are displayed as minisign does.
+fn format_key_id(key_id: &[u8]) -> String {
+    key_id
+        .iter()
+        .rev()
+        .map(|byte| format!("{:02X}", byte))
+        .collect()
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
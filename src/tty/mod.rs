@@ -3,13 +3,14 @@
 
 #[derive(Debug)]
 pub struct Width(pub u16);
+#[allow(dead_code)]
 #[derive(Debug)]
 pub struct Height(pub u16);
 
 #[cfg(unix)]
 mod unix;
 #[cfg(unix)]
-pub use self::unix::{terminal_size, terminal_size_using_fd};
+pub use self::unix::terminal_size;
 
 #[cfg(windows)]
 mod windows;
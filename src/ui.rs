@@ -3,24 +3,34 @@ use crossbeam::channel::{select, unbounded};
 use std::sync::Arc;
 
 use crate::hash_file_process::{
-    FileProgress, HashFileProcessResult, HashFileProcessType, HashFileProcessor,
+    FileProgress, HashFileProcessResult, HashFileProcessStats, HashFileProcessType,
+    HashFileProcessor,
 };
 use crate::output::Output;
+use crate::report_log::ReportLog;
 
 pub struct UI {
     processor: HashFileProcessor,
     silent: bool,
+    report_log: Option<ReportLog>,
 }
 
 impl UI {
     pub fn new(processor: HashFileProcessor, silent: bool) -> UI {
-        UI { processor, silent }
+        UI {
+            processor,
+            silent,
+            report_log: None,
+        }
+    }
+    pub fn set_report_log(&mut self, report_log: ReportLog) {
+        self.report_log = Some(report_log);
     }
     pub fn run(
         mut self,
         cancellation_token: Arc<CancellationToken>,
         process_type: HashFileProcessType,
-    ) -> HashFileProcessResult {
+    ) -> (HashFileProcessResult, HashFileProcessStats) {
         let silent = self.silent;
         let (error_sender, error_receiver) = unbounded();
         let (warning_sender, warning_receiver) = unbounded();
@@ -37,6 +47,11 @@ impl UI {
                 .set_complete_event_sender(complete_sender.clone());
         }
 
+        let mut report_log = self.report_log.take();
+        if let Some(report_log) = report_log.as_mut() {
+            log_write_result(report_log.write_start(process_type, self.processor.get_base_path()));
+        }
+
         let message_loop = std::thread::spawn(move || {
             let mut error_sender_dropped = false;
             let mut warning_sender_dropped = false;
@@ -54,7 +69,7 @@ impl UI {
                     recv(progress_receiver) -> msg => {
                         if let Ok(args) = msg {
                             if args.bytes_processed == 0 {
-                                if file_progress.file_path != "" && !skip_processed {
+                                if !file_progress.file_path.is_empty() && !skip_processed {
                                     output.write_processed(&file_progress.file_path);
                                 }
 
@@ -75,6 +90,9 @@ impl UI {
                         if let Ok(error) = msg {
                             skip_processed = true;
                             output.write_error(&error);
+                            if let Some(report_log) = report_log.as_mut() {
+                                log_write_result(report_log.write_error(&error));
+                            }
                         }
                         else {
                             error_sender_dropped = true;
@@ -84,6 +102,9 @@ impl UI {
                         if let Ok(warning) = msg {
                             skip_processed = true;
                             output.write_error(&warning);
+                            if let Some(report_log) = report_log.as_mut() {
+                                log_write_result(report_log.write_warning(&warning));
+                            }
                         } else {
                             warning_sender_dropped = true;
                         }
@@ -97,6 +118,8 @@ impl UI {
             if !silent && !skip_processed {
                 output.write_processed(&file_progress.file_path);
             }
+
+            report_log
         });
 
         let process = std::thread::spawn(move || {
@@ -106,15 +129,21 @@ impl UI {
             drop(error_sender);
             drop(warning_sender);
             drop(progress_sender);
-            result
+            (result, self.processor.get_stats().clone())
         });
 
-        message_loop.join().unwrap();
+        let report_log = message_loop.join().unwrap();
+        let (result, stats) = process.join().unwrap();
         if !silent {
             if let Ok(result) = complete_receiver.recv() {
                 let output = Output::new();
                 if result == HashFileProcessResult::Canceled {
                     output.clear_line();
+                } else if let Some(sample_seed) = stats.sample_seed {
+                    output.write_result(format!(
+                        "{:?} result: {:?} ({} of {} files, seed {})",
+                        process_type, result, stats.sampled, stats.sample_population, sample_seed
+                    ));
                 } else {
                     output.write_result(format!("{:?} result: {:?}", process_type, result));
                 }
@@ -122,6 +151,17 @@ impl UI {
         }
 
         drop(complete_sender);
-        process.join().unwrap()
+        // The result is logged even in silent mode, where no complete event is sent.
+        if let Some(mut report_log) = report_log {
+            log_write_result(report_log.write_result(process_type, result, &stats));
+        }
+
+        (result, stats)
+    }
+}
+
+fn log_write_result(result: std::io::Result<()>) {
+    if let Err(error) = result {
+        eprintln!("Couldn't write to log file: {}.", error);
     }
 }
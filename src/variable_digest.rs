@@ -0,0 +1,138 @@
+use blake2::{VarBlake2b, VarBlake2s};
+use digest::{Update, VariableOutput};
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+use std::convert::TryInto;
+
+use crate::reader_hash::ReaderDigest;
+use crate::HashType;
+
+const BLAKE2B_MAX_KEY_SIZE: usize = 64;
+const BLAKE2S_MAX_KEY_SIZE: usize = 32;
+const BLAKE3_KEY_SIZE: usize = 32;
+
+// Hash functions configured at runtime: digests with a chosen length, like
+// `b2sum -l` and `b3sum --length` produce, and keyed hash functions. BLAKE3
+// digests longer than the default use its extendable output function (XOF).
+pub enum VariableDigest {
+    Blake2b(VarBlake2b),
+    Blake2s(VarBlake2s),
+    Blake3(Box<blake3::Hasher>, usize),
+    HmacSha256(Box<Hmac<Sha256>>),
+}
+
+impl VariableDigest {
+    // `digest_length` is in bits. With a key, BLAKE2 and BLAKE3 use their keyed
+    // mode and SHA256 is computed as HMAC-SHA256.
+    pub fn new(
+        hash_type: HashType,
+        digest_length: usize,
+        key: Option<&[u8]>,
+    ) -> Result<Self, String> {
+        if !crate::is_valid_digest_length(hash_type, digest_length) {
+            return Err(format!(
+                "Unsupported digest length for {:?}: {}.",
+                hash_type, digest_length
+            ));
+        }
+
+        if let Some(key) = key {
+            if !crate::supports_key(hash_type) {
+                return Err(format!(
+                    "Keyed hashing isn't supported for {:?}.",
+                    hash_type
+                ));
+            }
+
+            if key.is_empty() {
+                return Err("The key is empty.".into());
+            }
+        }
+
+        let digest_size = digest_length / 8;
+        match (hash_type, key) {
+            (HashType::BLAKE2B, key) => {
+                let key = key.unwrap_or_default();
+                check_max_key_size(hash_type, key, BLAKE2B_MAX_KEY_SIZE)?;
+                Ok(VariableDigest::Blake2b(VarBlake2b::new_keyed(
+                    key,
+                    digest_size,
+                )))
+            }
+            (HashType::BLAKE2S, key) => {
+                let key = key.unwrap_or_default();
+                check_max_key_size(hash_type, key, BLAKE2S_MAX_KEY_SIZE)?;
+                Ok(VariableDigest::Blake2s(VarBlake2s::new_keyed(
+                    key,
+                    digest_size,
+                )))
+            }
+            (HashType::BLAKE3, Some(key)) => {
+                let key: &[u8; BLAKE3_KEY_SIZE] = key.try_into().map_err(|_| {
+                    format!("The BLAKE3 key must be {} bytes long.", BLAKE3_KEY_SIZE)
+                })?;
+                Ok(VariableDigest::Blake3(
+                    Box::new(blake3::Hasher::new_keyed(key)),
+                    digest_size,
+                ))
+            }
+            (HashType::BLAKE3, None) => Ok(VariableDigest::Blake3(
+                Box::new(blake3::Hasher::new()),
+                digest_size,
+            )),
+            (HashType::SHA256, Some(key)) => Hmac::<Sha256>::new_varkey(key)
+                .map(|hmac| VariableDigest::HmacSha256(Box::new(hmac)))
+                .map_err(|error| error.to_string()),
+            _ => Err(format!(
+                "Unsupported digest length for {:?}: {}.",
+                hash_type, digest_length
+            )),
+        }
+    }
+}
+
+fn check_max_key_size(hash_type: HashType, key: &[u8], max_key_size: usize) -> Result<(), String> {
+    if key.len() > max_key_size {
+        return Err(format!(
+            "The {:?} key must be at most {} bytes long.",
+            hash_type, max_key_size
+        ));
+    }
+
+    Ok(())
+}
+
+impl ReaderDigest for VariableDigest {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            VariableDigest::Blake2b(hasher) => Update::update(hasher, data),
+            VariableDigest::Blake2s(hasher) => Update::update(hasher, data),
+            VariableDigest::Blake3(hasher, _) => {
+                hasher.update(data);
+            }
+            VariableDigest::HmacSha256(hmac) => hmac.update(data),
+        }
+    }
+    fn finalize_hex(&mut self) -> String {
+        let mut digest = String::new();
+        match self {
+            VariableDigest::Blake2b(hasher) => {
+                hasher.finalize_variable_reset(|bytes| digest = hex::encode(bytes))
+            }
+            VariableDigest::Blake2s(hasher) => {
+                hasher.finalize_variable_reset(|bytes| digest = hex::encode(bytes))
+            }
+            VariableDigest::Blake3(hasher, digest_size) => {
+                let mut bytes = vec![0; *digest_size];
+                hasher.finalize_xof().fill(&mut bytes);
+                hasher.reset();
+                digest = hex::encode(bytes);
+            }
+            VariableDigest::HmacSha256(hmac) => {
+                digest = hex::encode(hmac.finalize_reset().into_bytes());
+            }
+        }
+
+        digest
+    }
+}
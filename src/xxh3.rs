@@ -0,0 +1,64 @@
+use digest::generic_array::typenum::{U16, U8};
+use digest::{FixedOutputDirty, Output, Reset, Update};
+use xxhash_rust::xxh3::Xxh3;
+
+// `digest` adapters for the XXH3 hash functions. Digests are encoded in
+// big-endian order (canonical representation), like `xxhsum` does.
+
+#[derive(Clone)]
+pub struct Xxh3_64(Xxh3);
+
+impl Default for Xxh3_64 {
+    fn default() -> Self {
+        Xxh3_64(Xxh3::new())
+    }
+}
+
+impl Update for Xxh3_64 {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.0.update(data.as_ref());
+    }
+}
+
+impl FixedOutputDirty for Xxh3_64 {
+    type OutputSize = U8;
+
+    fn finalize_into_dirty(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.0.digest().to_be_bytes());
+    }
+}
+
+impl Reset for Xxh3_64 {
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[derive(Clone)]
+pub struct Xxh3_128(Xxh3);
+
+impl Default for Xxh3_128 {
+    fn default() -> Self {
+        Xxh3_128(Xxh3::new())
+    }
+}
+
+impl Update for Xxh3_128 {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.0.update(data.as_ref());
+    }
+}
+
+impl FixedOutputDirty for Xxh3_128 {
+    type OutputSize = U16;
+
+    fn finalize_into_dirty(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.0.digest128().to_be_bytes());
+    }
+}
+
+impl Reset for Xxh3_128 {
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
@@ -35,3 +35,13 @@ pub fn get_file_string_content(path: &Path) -> String {
         .unwrap_or_else(|_| panic!("Failed to read the file content of {}.", path.display()));
     content
 }
+
+// Secret keys generated independently of minisign for tests, with the
+// same keypair, unencrypted and encrypted with "password".
+pub static MINISIGN_SECRET_KEY: &str = "untrusted comment: minisign secret key 0807060504030201
+RWQAAEIyVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVUAAAAAAAAAAAAAAAAAAAAAAQIDBAUGBwgAAQIDBAUGBwgJCgsMDQ4PEBESExQVFhcYGRobHB0eHwOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4UuhoZ0oj6XehkwAxqgcDfOhmwCrPXeYwQwAwVY5NB98=
+";
+pub static MINISIGN_ENCRYPTED_SECRET_KEY: &str = "untrusted comment: minisign encrypted secret key
+RWRTY0IyVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVUAgAAAAAAAAAAAAAEAAAAALXfqU57rrakWc0i3IJBKacj3UpmTunDQNGaduvUJixaK5DCawZLoMG/Ao0bzhQoqVRgp+xC5E4DhCaD2B7AnFfh1UGm+9wAEdsAbTgZI+x98hoP8QEo3VUC8bZ6VCa2ZThgqiBxpcYA=
+";
+pub static MINISIGN_PUBLIC_KEY: &str = "RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
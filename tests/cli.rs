@@ -0,0 +1,274 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+extern crate test_shared;
+
+static HASHCHECK_SHA1_NAME: &str = "hshchk.sha1";
+static HASHCHECK_SHA1_CONTENT: &str = "file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n";
+
+// Configuration files and environment variables of the user running the tests
+// must not affect the results.
+fn get_command() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hshchk"));
+    command.env(
+        "XDG_CONFIG_HOME",
+        std::env::temp_dir().join("hshchk-test-no-config"),
+    );
+    for (name, _) in std::env::vars().filter(|(name, _)| name.starts_with("HSHCHK_")) {
+        command.env_remove(name);
+    }
+
+    command
+}
+
+fn run_hshchk(args: &[&str], directory: &Path) -> Output {
+    get_command()
+        .args(args)
+        .arg(directory)
+        .output()
+        .expect("Failed to run hshchk.")
+}
+
+#[test]
+fn cli_exit_code_success() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    assert_eq!(run_hshchk(&["create"], &dir).status.code(), Some(0));
+    assert_eq!(run_hshchk(&["verify"], &dir).status.code(), Some(0));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_exit_code_mismatch() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "test");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        &format!(
+            "{}missing|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n",
+            HASHCHECK_SHA1_CONTENT
+        ),
+    );
+    let output = run_hshchk(&["-s"], &dir);
+    assert_eq!(output.status.code(), Some(2));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_exit_code_missing() {
+    let dir = test_shared::create_tmp_dir();
+    let _ =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    let _ = test_shared::create_file_with_content(&dir, "other", "data");
+    let output = run_hshchk(&["verify"], &dir);
+    assert_eq!(output.status.code(), Some(3));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_exit_code_io_error() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let output = run_hshchk(&["verify"], &dir);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(!dir.join(HASHCHECK_SHA1_NAME).exists());
+    let output = run_hshchk(&[], &dir.join("not_mounted"));
+    assert_eq!(output.status.code(), Some(4));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_exit_code_no_files_processed() {
+    let dir = test_shared::create_tmp_dir();
+    let output = run_hshchk(&["create"], &dir);
+    assert_eq!(output.status.code(), Some(5));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_exit_code_usage() {
+    let dir = test_shared::create_tmp_dir();
+    let output = run_hshchk(&["--unknown"], &dir);
+    assert_eq!(output.status.code(), Some(64));
+    let output = run_hshchk(&["--help"], &dir);
+    assert_eq!(output.status.code(), Some(0));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_config_precedence() {
+    let dir = test_shared::create_tmp_dir();
+    let config_dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    fs::create_dir(config_dir.join("hshchk")).expect("Failed to create config directory.");
+    let _ = test_shared::create_file_with_content(
+        &config_dir.join("hshchk"),
+        "config.toml",
+        "type = \"sha256\"\nsum = true\n",
+    );
+    let _ = test_shared::create_file_with_content(&dir, ".hshchk.toml", "type = \"md5\"\n");
+    let run = |args: &[&str], env_type: Option<&str>| {
+        let mut command = get_command();
+        command.env("XDG_CONFIG_HOME", &config_dir);
+        if let Some(env_type) = env_type {
+            command.env("HSHCHK_TYPE", env_type);
+        }
+        command
+            .args(args)
+            .arg(&dir)
+            .output()
+            .expect("Failed to run hshchk.")
+            .status
+            .code()
+    };
+    // The per-tree config overrides the user config.
+    assert_eq!(run(&["create"], None), Some(0));
+    assert!(dir.join("MD5SUMS").is_file());
+    // Environment variables override config files.
+    assert_eq!(run(&["create"], Some("blake3")), Some(0));
+    assert!(dir.join("BLAKE3SUMS").is_file());
+    // Command-line flags override everything.
+    assert_eq!(run(&["create", "-t", "sha512"], Some("blake3")), Some(0));
+    assert!(dir.join("SHA512SUMS").is_file());
+    // Invalid configuration.
+    assert_eq!(run(&["create"], Some("crc")), Some(78));
+    assert_eq!(run(&["create", "--exclude", "{a"], None), Some(78));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    fs::remove_dir_all(config_dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_flag_negation() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "date");
+    let _ =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    let exit_code = |args: &[&str]| {
+        get_command()
+            .env("HSHCHK_SIZE", "1")
+            .args(args)
+            .arg(&dir)
+            .output()
+            .expect("Failed to run hshchk.")
+            .status
+            .code()
+    };
+    assert_eq!(exit_code(&["verify", "-s"]), Some(0));
+    assert_eq!(exit_code(&["verify", "-s", "--no-size"]), Some(2));
+    // The last of a flag and its negation wins.
+    assert_eq!(exit_code(&["verify", "-s", "--no-size", "--size"]), Some(0));
+    assert_eq!(exit_code(&["verify", "-s", "--size", "--no-size"]), Some(2));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_digest_length_existing_hash_type() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let exit_code = |args: &[&str]| run_hshchk(args, &dir).status.code();
+    assert_eq!(
+        exit_code(&["create", "-t", "blake2b", "--length", "256"]),
+        Some(0)
+    );
+    // The length is checked against the hash type of the checksum file.
+    assert_eq!(exit_code(&["verify", "--length", "256"]), Some(0));
+    assert_eq!(exit_code(&["verify", "--length", "7"]), Some(64));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_tree_config_restricted() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "date");
+    let _ =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    // The tree being verified can't skip files or checks.
+    for tree_config in &[
+        "size = true\n",
+        "exclude = [\"file\"]\n",
+        "ignore = \"file\"\n",
+    ] {
+        let _ = test_shared::create_file_with_content(&dir, ".hshchk.toml", tree_config);
+        assert_eq!(run_hshchk(&["verify"], &dir).status.code(), Some(78));
+    }
+    let _ = test_shared::create_file_with_content(&dir, ".hshchk.toml", "size = false\n");
+    assert_eq!(run_hshchk(&["verify"], &dir).status.code(), Some(2));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_log() {
+    let dir = test_shared::create_tmp_dir();
+    let log_dir = test_shared::create_tmp_dir();
+    let log_file = log_dir.join("hshchk.log");
+    let _ = test_shared::create_file_with_content(&dir, "file", "test");
+    let _ =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    let output = run_hshchk(&["-s", "--log", log_file.to_str().unwrap()], &dir);
+    assert_eq!(output.status.code(), Some(2));
+    let _ = run_hshchk(&["--silent", "--log", log_file.to_str().unwrap()], &dir);
+    let log = test_shared::get_file_string_content(&log_file);
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(6, lines.len());
+    assert!(lines[0].ends_with(&format!(
+        "Verify started: {}",
+        fs::canonicalize(&dir).unwrap().display()
+    )));
+    assert!(lines[1].ends_with("ERROR file => IncorrectHash"));
+    assert!(lines[2].ends_with(
+        "Verify result: Error (files processed: 1, bytes processed: 4, mismatches: 1, \
+         missing: 0, errors: 0, warnings: 0)"
+    ));
+    for line in lines {
+        let timestamp = line.split(' ').next().unwrap();
+        assert!(humantime::parse_rfc3339(timestamp).is_ok());
+    }
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    fs::remove_dir_all(log_dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn cli_sign() {
+    let dir = test_shared::create_tmp_dir();
+    let key_dir = test_shared::create_tmp_dir();
+    let secret_key = test_shared::create_file_with_content(
+        &key_dir,
+        "minisign.key",
+        test_shared::MINISIGN_SECRET_KEY,
+    );
+    let public_key = test_shared::create_file_with_content(
+        &key_dir,
+        "minisign.pub",
+        test_shared::MINISIGN_PUBLIC_KEY,
+    );
+    let _ = test_shared::create_file_with_content(&dir, "file", "test");
+    let output = run_hshchk(&["sign", "--key", secret_key.to_str().unwrap()], &dir);
+    assert_eq!(output.status.code(), Some(4));
+    let _ = run_hshchk(&["create", "-s", "-t", "sha256"], &dir);
+    let output = run_hshchk(&["sign", "--key", secret_key.to_str().unwrap()], &dir);
+    assert_eq!(output.status.code(), Some(0));
+    let signature = test_shared::get_file_string_content(&dir.join("hshchk.sha256.minisig"));
+    assert!(signature
+        .lines()
+        .nth(2)
+        .unwrap()
+        .ends_with("\tfile:hshchk.sha256\thashed"));
+    let public_key_arg = ["-s", "--public-key", public_key.to_str().unwrap()];
+    let output = run_hshchk(&public_key_arg, &dir);
+    assert_eq!(output.status.code(), Some(0));
+    let _ = test_shared::create_file_with_content(&dir, "hshchk.sha256", "");
+    let output = run_hshchk(&public_key_arg, &dir);
+    assert_eq!(output.status.code(), Some(4));
+    // The public key can't be set by the tree being verified.
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        ".hshchk.toml",
+        &format!("public-key = {:?}\n", public_key.to_str().unwrap()),
+    );
+    let output = run_hshchk(&["-s"], &dir);
+    assert_eq!(output.status.code(), Some(78));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    fs::remove_dir_all(key_dir).expect("Failed to remove test directory.");
+}
@@ -1,9 +1,11 @@
 use crossbeam::channel::unbounded;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use hshchk::hash_file_process::*;
-use hshchk::{HashFileFormat, HashType};
+use hshchk::signature::{self, PublicKey, SecretKey};
+use hshchk::{HashFileCompression, HashFileFormat, HashType, SampleSize};
 
 extern crate test_shared;
 // #[path = "../src/test/mod.rs"]
@@ -16,6 +18,14 @@ static HASHCHECK_SHA1_CONTENT: &str = "file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9
 static HASHCHECK_MD5_CONTENT: &str = "file|4|8d777f385d3dfec8815d20f7496026dc\n";
 static HASHSUM_SHA1_CONTENT: &str = "a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd *file\n";
 
+fn set_file_age(file_path: &Path, days: u64) {
+    fs::File::options()
+        .write(true)
+        .open(file_path)
+        .and_then(|file| file.set_modified(SystemTime::now() - Duration::from_secs(days * 86_400)))
+        .expect("Failed to set file modification time.");
+}
+
 #[test]
 fn hash_file_process_create_no_files_processed() {
     let dir = test_shared::create_tmp_dir();
@@ -347,3 +357,1551 @@ fn hash_file_process_hashsum_verify() {
     assert!(receiver.try_recv().is_err());
     fs::remove_dir_all(dir).expect("Failed to remove test directory.");
 }
+
+#[cfg(unix)]
+#[test]
+fn hash_file_process_create_unreadable_file() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    std::os::unix::fs::symlink(dir.join("nowhere"), dir.join("broken"))
+        .expect("Failed to create symlink.");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    let (sender, receiver) = unbounded();
+    processor.set_error_event_sender(sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let error = receiver.recv().unwrap();
+    assert_eq!(PathBuf::from("broken"), error.file_path);
+    assert!(matches!(error.state, FileProcessState::Error(_)));
+    assert!(receiver.try_recv().is_err());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[cfg(unix)]
+#[test]
+fn hash_file_process_update_unreadable_file() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    std::os::unix::fs::symlink(dir.join("nowhere"), dir.join("broken"))
+        .expect("Failed to create symlink.");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        &format!(
+            "{}broken|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n",
+            HASHCHECK_SHA1_CONTENT
+        ),
+    );
+    for process_type in &[HashFileProcessType::Update, HashFileProcessType::Verify] {
+        let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+            base_path: dir.clone(),
+            process_type: Some(*process_type),
+            ..Default::default()
+        });
+        let (error_sender, error_receiver) = unbounded();
+        processor.set_error_event_sender(error_sender);
+        let (warning_sender, warning_receiver) = unbounded();
+        processor.set_warning_event_sender(warning_sender);
+        assert_eq!(processor.process(), HashFileProcessResult::Error);
+        // The entry is neither removed nor reported as missing.
+        let error = error_receiver.recv().unwrap();
+        assert_eq!(PathBuf::from("broken"), error.file_path);
+        assert!(matches!(error.state, FileProcessState::Error(_)));
+        assert!(error_receiver.try_recv().is_err());
+        assert!(warning_receiver.try_recv().is_err());
+        assert_eq!(0, processor.get_stats().missing);
+    }
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_ignore_file() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "ignored.log", "test");
+    let _ = test_shared::create_file_with_content(&dir, ".hshchkignore", "*.log\n");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let content = test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME));
+    assert!(content.contains(HASHCHECK_SHA1_CONTENT));
+    assert!(content.contains(".hshchkignore"));
+    assert!(!content.contains("ignored.log"));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_ignore_file_negation() {
+    let dir = test_shared::create_tmp_dir();
+    let sub_dir = dir.join("sub");
+    fs::create_dir(&sub_dir).expect("Failed to create sub directory.");
+    let _ = test_shared::create_file_with_content(&dir, ".hshchkignore", "*.log\n");
+    let _ = test_shared::create_file_with_content(&sub_dir, ".hshchkignore", "!keep.log\n");
+    let _ = test_shared::create_file_with_content(&sub_dir, "keep.log", "data");
+    let _ = test_shared::create_file_with_content(&sub_dir, "other.log", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let content = test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME));
+    assert!(content.contains("keep.log"));
+    assert!(!content.contains("other.log"));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_ignore_directory() {
+    let dir = test_shared::create_tmp_dir();
+    let sub_dir = dir.join("build");
+    fs::create_dir(&sub_dir).expect("Failed to create sub directory.");
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, ".hshchkignore", "/build/\n");
+    let _ = test_shared::create_file_with_content(&sub_dir, "output", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let content = test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME));
+    assert!(!content.contains("output"));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_gitignore() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "ignored.log", "test");
+    let _ = test_shared::create_file_with_content(&dir, ".gitignore", "*.log\n");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let content = test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME));
+    assert!(content.contains("ignored.log"));
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        force_create: Some(true),
+        use_gitignore: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let content = test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME));
+    assert!(!content.contains("ignored.log"));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_exclude_include() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "excluded.log", "test");
+    let _ = test_shared::create_file_with_content(&dir, "other.txt", "test");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        exclude_patterns: Some(vec!["*.log"]),
+        include_patterns: Some(vec!["file", "*.log"]),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let checksum_file = dir.join(HASHCHECK_SHA1_NAME);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_ignore_file() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        &(HASHCHECK_SHA1_CONTENT.to_owned()
+            + "ignored.log|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n"),
+    );
+    let _ = test_shared::create_file_with_content(&dir, "ignored.log", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        exclude_patterns: Some(vec!["*.log"]),
+        ..Default::default()
+    });
+    let (sender, receiver) = unbounded();
+    processor.set_error_event_sender(sender);
+    fs::remove_file(dir.join("ignored.log")).expect("Failed to remove ignored file.");
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert!(receiver.try_recv().is_err());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_match_relative_path() {
+    let dir = test_shared::create_tmp_dir();
+    let sub_dir = dir.join("sub");
+    fs::create_dir(&sub_dir).expect("Failed to create sub directory.");
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&sub_dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        match_pattern: Some("^file$"),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let checksum_file = dir.join(HASHCHECK_SHA1_NAME);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_match_normalized_separator() {
+    let dir = test_shared::create_tmp_dir();
+    let sub_dir = dir.join("sub");
+    fs::create_dir(&sub_dir).expect("Failed to create sub directory.");
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&sub_dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        match_pattern: Some("^sub/file$"),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let content = test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME));
+    assert_eq!(1, content.lines().count());
+    assert!(content.starts_with("sub"));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_match_relative_path() {
+    let dir = test_shared::create_tmp_dir();
+    let sub_dir = dir.join("sub");
+    fs::create_dir(&sub_dir).expect("Failed to create sub directory.");
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let sub_file = test_shared::create_file_with_content(&sub_dir, "file", "data");
+    let mut processor_create = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor_create.process(), HashFileProcessResult::Success);
+    fs::remove_file(sub_file).expect("Failed to remove unmatched file.");
+    let mut processor_verify = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        match_pattern: Some("^file$"),
+        ..Default::default()
+    });
+    assert_eq!(processor_verify.process(), HashFileProcessResult::Success);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_match_absolute_path() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        match_pattern: Some("^file$"),
+        match_absolute_path: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::NoFilesProcessed);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        match_pattern: Some(r"[/\\]file$"),
+        match_absolute_path: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let checksum_file = dir.join(HASHCHECK_SHA1_NAME);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_match_absolute_path() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let unmatched_file = test_shared::create_file_with_content(&dir, "unmatched", "test");
+    let mut processor_create = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor_create.process(), HashFileProcessResult::Success);
+    fs::remove_file(unmatched_file).expect("Failed to remove unmatched file.");
+    let mut processor_verify = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        match_pattern: Some(r"[/\\]file$"),
+        match_absolute_path: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(processor_verify.process(), HashFileProcessResult::Success);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_min_size() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "small", "d");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        min_size: Some(2),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let checksum_file = dir.join(HASHCHECK_SHA1_NAME);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_max_size() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "large", "datadata");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        max_size: Some(4),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let checksum_file = dir.join(HASHCHECK_SHA1_NAME);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_newer_than() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let old_file = test_shared::create_file_with_content(&dir, "old", "data");
+    set_file_age(&old_file, 30);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        newer_than: Some(SystemTime::now() - Duration::from_secs(7 * 86_400)),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let checksum_file = dir.join(HASHCHECK_SHA1_NAME);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_older_than() {
+    let dir = test_shared::create_tmp_dir();
+    let file = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "new", "data");
+    set_file_age(&file, 30);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        older_than: Some(SystemTime::now() - Duration::from_secs(7 * 86_400)),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let checksum_file = dir.join(HASHCHECK_SHA1_NAME);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_min_size_not_missing() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        &(HASHCHECK_SHA1_CONTENT.to_owned() + "small|1|e1b8a2d64df4c4c7a1c4b7d7b3a4f0a6f7e1b2c3\n"),
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        min_size: Some(2),
+        ..Default::default()
+    });
+    let (sender, receiver) = unbounded();
+    processor.set_error_event_sender(sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert!(receiver.try_recv().is_err());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_min_size_truncated() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "d");
+    let _ =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        min_size: Some(2),
+        ..Default::default()
+    });
+    let (sender, receiver) = unbounded();
+    processor.set_error_event_sender(sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    assert_eq!(
+        FileProcessEntry {
+            file_path: PathBuf::from("file"),
+            state: FileProcessState::IncorrectSize
+        },
+        receiver.recv().unwrap()
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_newer_than() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let old_file = test_shared::create_file_with_content(&dir, "old", "tada");
+    set_file_age(&old_file, 30);
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        &(HASHCHECK_SHA1_CONTENT.to_owned() + "old|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n"),
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        newer_than: Some(SystemTime::now() - Duration::from_secs(7 * 86_400)),
+        ..Default::default()
+    });
+    let (sender, receiver) = unbounded();
+    processor.set_error_event_sender(sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert!(receiver.try_recv().is_err());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_file_list() {
+    let dir = test_shared::create_tmp_dir();
+    let file = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "unlisted", "test");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        file_list: Some(vec![file, dir.clone()]),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let checksum_file = dir.join(HASHCHECK_SHA1_NAME);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_file_list() {
+    let dir = test_shared::create_tmp_dir();
+    let file = test_shared::create_file_with_content(&dir, "file", "data");
+    let unlisted_file = test_shared::create_file_with_content(&dir, "unlisted", "test");
+    let mut processor_create = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor_create.process(), HashFileProcessResult::Success);
+    fs::remove_file(unlisted_file).expect("Failed to remove unlisted file.");
+    let mut processor_verify = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        file_list: Some(vec![file]),
+        ..Default::default()
+    });
+    let (sender, receiver) = unbounded();
+    processor_verify.set_error_event_sender(sender);
+    assert_eq!(processor_verify.process(), HashFileProcessResult::Success);
+    assert!(receiver.try_recv().is_err());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_file_list_relative() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    // Relative paths are resolved against the target directory, not the current one.
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        file_list: Some(vec![PathBuf::from("./file")]),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(1, processor.get_stats().files_processed);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[cfg(unix)]
+#[test]
+fn hash_file_process_verify_unreadable_and_missing_files() {
+    let dir = test_shared::create_tmp_dir();
+    std::os::unix::fs::symlink(dir.join("nowhere"), dir.join("broken"))
+        .expect("Failed to create symlink.");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        &format!(
+            "{}broken|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n",
+            HASHCHECK_SHA1_CONTENT
+        ),
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let error = error_receiver.recv().unwrap();
+    assert_eq!(PathBuf::from("broken"), error.file_path);
+    assert!(matches!(error.state, FileProcessState::Error(_)));
+    assert_eq!(
+        FileProcessEntry {
+            file_path: PathBuf::from("file"),
+            state: FileProcessState::Missing
+        },
+        error_receiver.recv().unwrap()
+    );
+    assert_eq!(1, processor.get_stats().missing);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_update_file_list_partial() {
+    let dir = test_shared::create_tmp_dir();
+    let file = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "file2", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let content = test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME));
+    // Entries of unlisted files are kept.
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        process_type: Some(HashFileProcessType::Update),
+        file_list: Some(vec![file]),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let mut updated_lines: Vec<String> =
+        test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME))
+            .lines()
+            .map(String::from)
+            .collect();
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    updated_lines.sort();
+    lines.sort();
+    assert_eq!(lines, updated_lines);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn hash_file_process_create_file_list_ill_formed_unicode() {
+    use std::os::unix::ffi::OsStrExt;
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let invalid_file_name = std::ffi::OsStr::from_bytes(b"file\xff");
+    fs::write(dir.join(invalid_file_name), "data").expect("Failed to create file.");
+    let file_list = hshchk::get_file_list(&b"file\xff\0file\0"[..]).unwrap();
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        file_list: Some(file_list),
+        ..Default::default()
+    });
+    let (warning_sender, warning_receiver) = unbounded();
+    processor.set_warning_event_sender(warning_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let warning = warning_receiver.recv().unwrap();
+    assert_eq!(FileProcessState::InvalidUnicodeFileName, warning.state);
+    assert_eq!(
+        test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME)),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_file_list_missing() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "other", "data");
+    let _ =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        file_list: Some(vec![dir.join("file")]),
+        ..Default::default()
+    });
+    let (sender, receiver) = unbounded();
+    processor.set_error_event_sender(sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    assert_eq!(
+        FileProcessEntry {
+            file_path: PathBuf::from("file"),
+            state: FileProcessState::Missing
+        },
+        receiver.recv().unwrap()
+    );
+    assert!(receiver.try_recv().is_err());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_file_list_outside() {
+    let dir = test_shared::create_tmp_dir();
+    let outside_dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let outside_file = test_shared::create_file_with_content(&outside_dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        file_list: Some(vec![outside_file.clone()]),
+        ..Default::default()
+    });
+    let (sender, receiver) = unbounded();
+    processor.set_error_event_sender(sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let error = receiver.recv().unwrap();
+    assert_eq!(outside_file, error.file_path);
+    assert!(matches!(error.state, FileProcessState::Error(_)));
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    fs::remove_dir_all(outside_dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_no_checksum_file() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        process_type: Some(HashFileProcessType::Verify),
+        ..Default::default()
+    });
+    let (sender, receiver) = unbounded();
+    processor.set_error_event_sender(sender);
+    assert_eq!(processor.get_process_type(), HashFileProcessType::Verify);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let error = receiver.recv().unwrap();
+    assert_eq!(PathBuf::from(HASHCHECK_SHA1_NAME), error.file_path);
+    assert!(matches!(error.state, FileProcessState::Error(_)));
+    assert!(!dir.join(HASHCHECK_SHA1_NAME).exists());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_explicit() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let checksum_file = test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, "test");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        process_type: Some(HashFileProcessType::Create),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_update() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "added", "test");
+    let _ = test_shared::create_file_with_content(&dir, "resized", "tests");
+    let checksum_file = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        &format!(
+            "{}removed|4|a94a8fe5ccb19ba61c4c0873d391e987982fbbd3\n\
+             resized|4|a94a8fe5ccb19ba61c4c0873d391e987982fbbd3\n",
+            HASHCHECK_SHA1_CONTENT
+        ),
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        process_type: Some(HashFileProcessType::Update),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    let (warning_sender, warning_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    processor.set_warning_event_sender(warning_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert!(error_receiver.try_recv().is_err());
+    let mut warnings: Vec<FileProcessEntry> = warning_receiver.try_iter().collect();
+    warnings.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    assert_eq!(
+        vec![
+            FileProcessEntry {
+                file_path: PathBuf::from("added"),
+                state: FileProcessState::Added
+            },
+            FileProcessEntry {
+                file_path: PathBuf::from("removed"),
+                state: FileProcessState::Removed
+            },
+            FileProcessEntry {
+                file_path: PathBuf::from("resized"),
+                state: FileProcessState::Updated
+            },
+        ],
+        warnings
+    );
+    let mut lines: Vec<String> = test_shared::get_file_string_content(&checksum_file)
+        .lines()
+        .map(String::from)
+        .collect();
+    lines.sort();
+    assert_eq!(
+        vec![
+            "added|4|a94a8fe5ccb19ba61c4c0873d391e987982fbbd3",
+            "file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd",
+            "resized|5|04d13fd0aa6f0197cf2c999019a607c36c81eb9f",
+        ],
+        lines
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_update_keeps_unchanged_entries() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    // The stored digest is stale on purpose: unchanged sizes are not rehashed.
+    let stale_content = "file|4|0000000000000000000000000000000000000000\n";
+    let checksum_file =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, stale_content);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        process_type: Some(HashFileProcessType::Update),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        stale_content
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_diff() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "extra", "test");
+    let checksum_file =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        process_type: Some(HashFileProcessType::Diff),
+        ..Default::default()
+    });
+    let (warning_sender, warning_receiver) = unbounded();
+    processor.set_warning_event_sender(warning_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(
+        FileProcessEntry {
+            file_path: PathBuf::from("extra"),
+            state: FileProcessState::Extra
+        },
+        warning_receiver.recv().unwrap()
+    );
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        HASHCHECK_SHA1_CONTENT
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_stream() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "changed", "date");
+    let _ = test_shared::create_file_with_content(&dir, "extra", "test");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        "file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n\
+         changed|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n\
+         missing|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n",
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        stream: Some(true),
+        report_extra: Some(true),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    let (warning_sender, warning_receiver) = unbounded();
+    processor.set_warning_event_sender(warning_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    assert_eq!(
+        FileProcessEntry {
+            file_path: PathBuf::from("changed"),
+            state: FileProcessState::IncorrectHash
+        },
+        error_receiver.recv().unwrap()
+    );
+    assert_eq!(
+        FileProcessEntry {
+            file_path: PathBuf::from("missing"),
+            state: FileProcessState::Missing
+        },
+        error_receiver.recv().unwrap()
+    );
+    assert!(error_receiver.try_recv().is_err());
+    assert_eq!(
+        FileProcessEntry {
+            file_path: PathBuf::from("extra"),
+            state: FileProcessState::Extra
+        },
+        warning_receiver.recv().unwrap()
+    );
+    assert!(warning_receiver.try_recv().is_err());
+    let stats = processor.get_stats();
+    assert_eq!(2, stats.files_processed);
+    assert_eq!(1, stats.mismatches);
+    assert_eq!(1, stats.missing);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_stream_malformed() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        "file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\nfile|4\n",
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        stream: Some(true),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let error = error_receiver.recv().unwrap();
+    assert_eq!(Path::new(HASHCHECK_SHA1_NAME), error.file_path);
+    assert!(error_receiver.try_recv().is_err());
+    assert_eq!(1, processor.get_stats().files_processed);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_no_walk() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&dir, "a.txt", "date");
+    let _ = test_shared::create_file_with_content(&dir, "extra", "test");
+    fs::create_dir_all(dir.join("a")).unwrap();
+    fs::create_dir_all(dir.join("b")).unwrap();
+    let _ = test_shared::create_file_with_content(&dir.join("a"), "file", "date");
+    let _ = test_shared::create_file_with_content(&dir.join("b"), "file", "date");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        "b/file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n\
+         missing|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n\
+         a/file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n\
+         file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n\
+         a.txt|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n",
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        no_walk: Some(true),
+        report_extra: Some(true),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    let (warning_sender, warning_receiver) = unbounded();
+    processor.set_warning_event_sender(warning_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let errors: Vec<FileProcessEntry> = error_receiver.try_iter().collect();
+    assert_eq!(
+        vec![
+            FileProcessEntry {
+                file_path: PathBuf::from("a.txt"),
+                state: FileProcessState::IncorrectHash
+            },
+            FileProcessEntry {
+                file_path: PathBuf::from("missing"),
+                state: FileProcessState::Missing
+            },
+            FileProcessEntry {
+                file_path: Path::new("a").join("file"),
+                state: FileProcessState::IncorrectHash
+            },
+            FileProcessEntry {
+                file_path: Path::new("b").join("file"),
+                state: FileProcessState::IncorrectHash
+            },
+        ],
+        errors
+    );
+    assert!(warning_receiver.try_recv().is_err());
+    assert_eq!(4, processor.get_stats().files_processed);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_verify_per_directory() {
+    let dir = test_shared::create_tmp_dir();
+    let sub_dir = dir.join("sub");
+    let deeper_dir = sub_dir.join("deeper");
+    fs::create_dir_all(&deeper_dir).unwrap();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&sub_dir, "file", "data");
+    let deeper_file = test_shared::create_file_with_content(&deeper_dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        per_directory: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    for hash_file_dir in &[&dir, &sub_dir, &deeper_dir] {
+        assert_eq!(
+            test_shared::get_file_string_content(&hash_file_dir.join(HASHCHECK_SHA1_NAME)),
+            HASHCHECK_SHA1_CONTENT
+        );
+    }
+
+    // Subdirectories can be verified on their own.
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: sub_dir.clone(),
+        per_directory: Some(true),
+        report_extra: Some(true),
+        ..Default::default()
+    });
+    let (warning_sender, warning_receiver) = unbounded();
+    processor.set_warning_event_sender(warning_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(processor.get_process_type(), HashFileProcessType::Verify);
+    assert_eq!(2, processor.get_stats().files_processed);
+    assert!(warning_receiver.try_recv().is_err());
+    fs::write(&deeper_file, "date").unwrap();
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        per_directory: Some(true),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    assert_eq!(
+        FileProcessEntry {
+            file_path: Path::new("sub").join("deeper").join("file"),
+            state: FileProcessState::IncorrectHash
+        },
+        error_receiver.recv().unwrap()
+    );
+    assert!(error_receiver.try_recv().is_err());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_subdirectory_hash_files() {
+    let dir = test_shared::create_tmp_dir();
+    let sub_dir = dir.join("sub");
+    fs::create_dir_all(&sub_dir).unwrap();
+    let _ = test_shared::create_file_with_content(&sub_dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&sub_dir, "extra", "test");
+    let _ =
+        test_shared::create_file_with_content(&sub_dir, HASHSUM_SHA1_NAME, HASHSUM_SHA1_CONTENT);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        process_type: Some(HashFileProcessType::Update),
+        ..Default::default()
+    });
+    let (warning_sender, warning_receiver) = unbounded();
+    processor.set_warning_event_sender(warning_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(
+        FileProcessEntry {
+            file_path: Path::new("sub").join("extra"),
+            state: FileProcessState::Added
+        },
+        warning_receiver.recv().unwrap()
+    );
+    let content = test_shared::get_file_string_content(&sub_dir.join(HASHSUM_SHA1_NAME));
+    assert!(content.contains(" *extra\n"));
+    assert!(!dir.join(HASHSUM_SHA1_NAME).exists());
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        process_type: Some(HashFileProcessType::Verify),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(2, processor.get_stats().files_processed);
+    assert!(!dir.join(HASHCHECK_SHA1_NAME).exists());
+    // Without an explicit process type, checksum files of subdirectories are ignored.
+    let processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.get_process_type(), HashFileProcessType::Create);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_per_directory_mixed() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    let sub_dir = dir.join("sub");
+    fs::create_dir_all(&sub_dir).unwrap();
+    let _ = test_shared::create_file_with_content(&sub_dir, "file", "data");
+    let _ =
+        test_shared::create_file_with_content(&sub_dir, HASHCHECK_MD5_NAME, HASHCHECK_MD5_CONTENT);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        per_directory: Some(true),
+        process_type: Some(HashFileProcessType::Verify),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let error = error_receiver.recv().unwrap();
+    assert_eq!(Path::new("sub").join(HASHCHECK_MD5_NAME), error.file_path);
+    assert_eq!(0, processor.get_stats().files_processed);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_all() {
+    let dir = test_shared::create_tmp_dir();
+    let sub_dir = dir.join("sub");
+    let deeper_dir = sub_dir.join("deeper");
+    fs::create_dir_all(&deeper_dir).unwrap();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        "SHA256SUMS",
+        "3a6eb0790f39ac87c94f3856b2dd2c5d110e6811602261a9a923d3bb23adc8b7  file\n",
+    );
+    let _ = test_shared::create_file_with_content(&sub_dir, "file.iso", "data");
+    let _ = test_shared::create_file_with_content(
+        &sub_dir,
+        "file.iso.md5",
+        "8d777f385d3dfec8815d20f7496026dc  file.iso\n",
+    );
+    let _ = test_shared::create_file_with_content(&deeper_dir, "file", "data");
+    let _ = test_shared::create_file_with_content(&deeper_dir, "changed", "date");
+    let _ = test_shared::create_file_with_content(
+        &deeper_dir,
+        "files.sfv",
+        "; comment\nfile ADF3F363\nchanged ADF3F363\n",
+    );
+    let _ = test_shared::create_file_with_content(&deeper_dir, "broken.sha1", "broken\n");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        verify_all: Some(true),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.get_process_type(), HashFileProcessType::Verify);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let errors: Vec<FileProcessEntry> = error_receiver.try_iter().collect();
+    assert_eq!(2, errors.len());
+    assert_eq!(
+        Path::new("sub").join("deeper").join("broken.sha1"),
+        errors[0].file_path
+    );
+    assert_eq!(
+        FileProcessEntry {
+            file_path: Path::new("sub").join("deeper").join("changed"),
+            state: FileProcessState::IncorrectHash
+        },
+        errors[1]
+    );
+    assert_eq!(4, processor.get_stats().files_processed);
+    assert!(!dir.join(HASHCHECK_SHA1_NAME).exists());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_hashsum_verify_sha384() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        "SHA384SUMS",
+        "2039e0f0b92728499fb88e23ebc3cfd0554b28400b0ed7b753055c88b5865c3c\
+         2aa72c6a1a9ae0a755d87900a4a6ff41  file\n",
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.get_process_type(), HashFileProcessType::Verify);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_sha3_256() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        hash_type: Some(HashType::SHA3_256),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(
+        test_shared::get_file_string_content(&dir.join("hshchk.sha3-256")),
+        "file|4|efda893aa850b0c0e61f33325615b9d93bcf6b42d60d8f5d37ebc720fd4e3daf\n"
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_hashsum_xxh3() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        hash_type: Some(HashType::XXH3_64),
+        hash_file_format: Some(HashFileFormat::HashSum),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let checksum_file = dir.join("XXH3SUMS");
+    assert_eq!(
+        test_shared::get_file_string_content(&checksum_file),
+        "XXH3_2d06800538d394c2  file\n"
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.get_process_type(), HashFileProcessType::Verify);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_digest_length() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        hash_type: Some(HashType::BLAKE2B),
+        digest_length: Some(256),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(
+        test_shared::get_file_string_content(&dir.join("hshchk.blake2b")),
+        "file|4|a035872d6af8639ede962dfe7536b0c150b590f3234a922fb7064cd11971b58e\n"
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_inferred_digest_length() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        "BLAKE2BSUMS",
+        "a035872d6af8639ede962dfe7536b0c150b590f3234a922fb7064cd11971b58e  file\n",
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_verify_keyed() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        hash_type: Some(HashType::SHA256),
+        key: Some(b"key".to_vec()),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    assert_eq!(
+        test_shared::get_file_string_content(&dir.join("hshchk.sha256")),
+        "# keyed: HMAC-SHA256\n\
+         file|4|5031fe3d989c6d1537a013fa6e739da23463fdaec3b70137d828e36ace221bd0\n"
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        key: Some(b"key".to_vec()),
+        ..Default::default()
+    });
+    assert_eq!(processor.get_process_type(), HashFileProcessType::Verify);
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        key: Some(b"other key".to_vec()),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_keyed_without_key() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        "hshchk.sha256",
+        "# keyed: HMAC-SHA256\n\
+         file|4|5031fe3d989c6d1537a013fa6e739da23463fdaec3b70137d828e36ace221bd0\n",
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    match error_receiver.recv().unwrap().state {
+        FileProcessState::Error(message) => assert!(message.contains("A key is required")),
+        _ => panic!("Unexpected error state."),
+    }
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_signature() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let hash_file =
+        test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, HASHCHECK_SHA1_CONTENT);
+    let new_processor = || {
+        HashFileProcessor::new(HashFileProcessOptions {
+            base_path: dir.clone(),
+            public_key: Some(PublicKey::decode(test_shared::MINISIGN_PUBLIC_KEY).unwrap()),
+            ..Default::default()
+        })
+    };
+    assert_eq!(new_processor().process(), HashFileProcessResult::Error);
+    let secret_key = SecretKey::decode(test_shared::MINISIGN_SECRET_KEY, || {
+        panic!("Unexpected password request.")
+    })
+    .unwrap();
+    let signature_file = signature::sign_file(&hash_file, &secret_key).unwrap();
+    assert_eq!(dir.join("hshchk.sha1.minisig"), signature_file);
+    assert_eq!(new_processor().process(), HashFileProcessResult::Success);
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        "file|4|0000000000000000000000000000000000000000\n",
+    );
+    let mut processor = new_processor();
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let error = error_receiver.recv().unwrap();
+    assert_eq!(Path::new(HASHCHECK_SHA1_NAME), error.file_path);
+    assert!(error_receiver.try_recv().is_err());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_verify_header() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        hash_type: Some(HashType::SHA256),
+        hash_file_format: Some(HashFileFormat::HashSum),
+        header: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let hash_file = dir.join("SHA256SUMS");
+    let content = test_shared::get_file_string_content(&hash_file);
+    assert!(content.contains("# format: sum\n# algorithm: SHA256\n"));
+    assert!(content
+        .ends_with("3a6eb0790f39ac87c94f3856b2dd2c5d110e6811602261a9a923d3bb23adc8b7 *file\n"));
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    fs::write(
+        &hash_file,
+        content.replace("# entries: 1\n", "# entries: 2\n"),
+    )
+    .unwrap();
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    fs::write(&hash_file, content.replace("SHA256", "SHA512")).unwrap();
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_create_verify_compressed() {
+    for (hash_file_compression, hash_file_name) in &[
+        (HashFileCompression::Gzip, "SHA256SUMS.gz"),
+        (HashFileCompression::Zstd, "SHA256SUMS.zst"),
+    ] {
+        let dir = test_shared::create_tmp_dir();
+        let file = test_shared::create_file_with_content(&dir, "file", "data");
+        let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+            base_path: dir.clone(),
+            hash_type: Some(HashType::SHA256),
+            hash_file_format: Some(HashFileFormat::HashSum),
+            hash_file_compression: Some(*hash_file_compression),
+            header: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(processor.process(), HashFileProcessResult::Success);
+        assert!(dir.join(hash_file_name).is_file());
+        assert!(!dir.join("SHA256SUMS").exists());
+        let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+            base_path: dir.clone(),
+            report_extra: Some(true),
+            ..Default::default()
+        });
+        let (warning_sender, warning_receiver) = unbounded();
+        processor.set_warning_event_sender(warning_sender);
+        assert_eq!(processor.process(), HashFileProcessResult::Success);
+        assert!(warning_receiver.try_recv().is_err());
+        fs::write(&file, "date").unwrap();
+        let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+            base_path: dir.clone(),
+            ..Default::default()
+        });
+        assert_eq!(processor.process(), HashFileProcessResult::Error);
+        fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+    }
+}
+
+#[test]
+fn hash_file_process_create_verify_comment_like_file_name() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "#file", "data");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Success);
+    let content = test_shared::get_file_string_content(&dir.join(HASHCHECK_SHA1_NAME));
+    assert!(content.starts_with("./#file|"));
+    let _ = test_shared::create_file_with_content(&dir, "#file", "date");
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    assert_eq!(
+        FileProcessEntry {
+            file_path: PathBuf::from("#file"),
+            state: FileProcessState::IncorrectHash
+        },
+        error_receiver.recv().unwrap()
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_legacy_comment_like_file_name() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "#file", "date");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        "#file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n",
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    assert_eq!(
+        FileProcessEntry {
+            file_path: PathBuf::from("#file"),
+            state: FileProcessState::IncorrectHash
+        },
+        error_receiver.recv().unwrap()
+    );
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_unsafe_paths() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        "file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n\
+         ../file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n",
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let error = error_receiver.recv().unwrap();
+    assert_eq!(Path::new(HASHCHECK_SHA1_NAME), error.file_path);
+    match error.state {
+        FileProcessState::Error(message) => assert!(message.contains("line 2: ")),
+        _ => panic!("Unexpected error state."),
+    }
+    assert!(error_receiver.try_recv().is_err());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_malformed_digest() {
+    let dir = test_shared::create_tmp_dir();
+    let _ = test_shared::create_file_with_content(&dir, "file", "data");
+    let _ = test_shared::create_file_with_content(
+        &dir,
+        HASHCHECK_SHA1_NAME,
+        "file|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bb\n",
+    );
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        ..Default::default()
+    });
+    let (error_sender, error_receiver) = unbounded();
+    processor.set_error_event_sender(error_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Error);
+    let error = error_receiver.recv().unwrap();
+    assert_eq!(Path::new(HASHCHECK_SHA1_NAME), error.file_path);
+    match error.state {
+        FileProcessState::Error(message) => assert_eq!(
+            "Checksum file contains malformed lines \
+             (line 1: SHA1 digest length must be 160 bits, found 156).",
+            message
+        ),
+        _ => panic!("Unexpected error state."),
+    }
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_sample() {
+    let dir = test_shared::create_tmp_dir();
+    let mut hash_file_content = String::new();
+    for index in 0..10 {
+        let file_name = format!("file{}", index);
+        let _ = test_shared::create_file_with_content(&dir, &file_name, "data");
+        hash_file_content.push_str(&format!(
+            "{}|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n",
+            file_name
+        ));
+    }
+    let _ = test_shared::create_file_with_content(&dir, "extra", "data");
+    let _ = test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, &hash_file_content);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        sample: Some(SampleSize::Count(3)),
+        seed: Some(42),
+        report_extra: Some(true),
+        ..Default::default()
+    });
+    let (warning_sender, warning_receiver) = unbounded();
+    processor.set_warning_event_sender(warning_sender);
+    assert_eq!(processor.process(), HashFileProcessResult::Sampled);
+    assert!(warning_receiver.try_recv().is_err());
+    let stats = processor.get_stats();
+    assert_eq!(3, stats.files_processed);
+    assert_eq!(3, stats.sampled);
+    assert_eq!(10, stats.sample_population);
+    assert_eq!(Some(42), stats.sample_seed);
+    let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+        base_path: dir.clone(),
+        sample: Some(SampleSize::Percent(1.0)),
+        ..Default::default()
+    });
+    assert_eq!(processor.process(), HashFileProcessResult::Sampled);
+    assert_eq!(1, processor.get_stats().files_processed);
+    assert!(processor.get_stats().sample_seed.is_some());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}
+
+#[test]
+fn hash_file_process_verify_sample_seed() {
+    let dir = test_shared::create_tmp_dir();
+    let mut hash_file_content = String::new();
+    for index in 0..10 {
+        let file_name = format!("file{}", index);
+        let _ = test_shared::create_file_with_content(&dir, &file_name, "date");
+        hash_file_content.push_str(&format!(
+            "{}|4|a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd\n",
+            file_name
+        ));
+    }
+    let _ = test_shared::create_file_with_content(&dir, HASHCHECK_SHA1_NAME, &hash_file_content);
+    let verify_sample = || {
+        let mut processor = HashFileProcessor::new(HashFileProcessOptions {
+            base_path: dir.clone(),
+            sample: Some(SampleSize::Count(4)),
+            seed: Some(7),
+            ..Default::default()
+        });
+        let (error_sender, error_receiver) = unbounded();
+        processor.set_error_event_sender(error_sender);
+        assert_eq!(processor.process(), HashFileProcessResult::Error);
+        error_receiver.try_iter().collect::<Vec<FileProcessEntry>>()
+    };
+    let errors = verify_sample();
+    assert_eq!(4, errors.len());
+    assert_eq!(errors, verify_sample());
+    fs::remove_dir_all(dir).expect("Failed to remove test directory.");
+}